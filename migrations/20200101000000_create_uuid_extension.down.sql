@@ -0,0 +1 @@
+drop extension if exists "uuid-ossp";
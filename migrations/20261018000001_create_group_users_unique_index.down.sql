@@ -0,0 +1 @@
+drop index group_users_group_id_user_id_idx;
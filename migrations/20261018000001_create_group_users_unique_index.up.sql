@@ -0,0 +1 @@
+create unique index group_users_group_id_user_id_idx on group_users (group_id, user_id);
@@ -0,0 +1,2 @@
+alter table group_users
+  drop column role;
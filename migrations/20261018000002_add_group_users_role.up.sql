@@ -0,0 +1,3 @@
+alter table group_users
+  add column role text not null default 'member'
+  check (role in ('owner', 'admin', 'member'));
@@ -0,0 +1,2 @@
+drop index users_auth0_id_index_idx;
+alter table users drop column auth0_id_index;
@@ -0,0 +1,2 @@
+alter table users add column auth0_id_index text;
+create unique index users_auth0_id_index_idx on users (auth0_id_index);
@@ -0,0 +1,7 @@
+drop index users_auth0_id_index_idx;
+create unique index users_auth0_id_index_idx on users (auth0_id_index);
+drop index users_auth0_id_key;
+alter table users add constraint users_auth0_id_key unique (auth0_id);
+
+drop index users_deleted_at_idx;
+alter table users drop column deleted_at;
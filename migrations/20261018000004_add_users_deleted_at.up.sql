@@ -0,0 +1,8 @@
+alter table users add column deleted_at timestamptz;
+create index users_deleted_at_idx on users (deleted_at) where deleted_at is not null;
+
+-- a deleted user's auth0_id may sign up again while the old row awaits purging
+alter table users drop constraint users_auth0_id_key;
+create unique index users_auth0_id_key on users (auth0_id) where deleted_at is null;
+drop index users_auth0_id_index_idx;
+create unique index users_auth0_id_index_idx on users (auth0_id_index) where deleted_at is null;
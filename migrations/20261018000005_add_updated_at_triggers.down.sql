@@ -0,0 +1,5 @@
+drop trigger group_users_set_updated_at on group_users;
+drop trigger groups_set_updated_at on groups;
+drop trigger users_set_updated_at on users;
+
+drop function set_updated_at();
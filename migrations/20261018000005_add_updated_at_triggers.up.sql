@@ -0,0 +1,19 @@
+-- stamps updated_at on writes that don't set it themselves, such as manual
+-- fixes and bulk updates that bypass the models
+create function set_updated_at() returns trigger as $$
+begin
+  if new.updated_at is not distinct from old.updated_at then
+    new.updated_at = now();
+  end if;
+  return new;
+end;
+$$ language plpgsql;
+
+create trigger users_set_updated_at before update on users
+  for each row execute function set_updated_at();
+
+create trigger groups_set_updated_at before update on groups
+  for each row execute function set_updated_at();
+
+create trigger group_users_set_updated_at before update on group_users
+  for each row execute function set_updated_at();
@@ -0,0 +1,7 @@
+drop trigger groups_bump_version on groups;
+drop trigger users_bump_version on users;
+
+drop function bump_version();
+
+alter table groups drop column version;
+alter table users drop column version;
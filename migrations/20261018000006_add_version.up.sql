@@ -0,0 +1,19 @@
+alter table users add column version integer not null default 1;
+alter table groups add column version integer not null default 1;
+
+-- bumps the version, which the ETag is derived from, on writes that don't
+-- bump it themselves
+create function bump_version() returns trigger as $$
+begin
+  if new.version is not distinct from old.version then
+    new.version = old.version + 1;
+  end if;
+  return new;
+end;
+$$ language plpgsql;
+
+create trigger users_bump_version before update on users
+  for each row execute function bump_version();
+
+create trigger groups_bump_version before update on groups
+  for each row execute function bump_version();
@@ -0,0 +1,17 @@
+create or replace function bump_version() returns trigger as $$
+begin
+  if new.version is not distinct from old.version then
+    new.version = old.version + 1;
+  end if;
+  return new;
+end;
+$$ language plpgsql;
+
+create or replace function set_updated_at() returns trigger as $$
+begin
+  if new.updated_at is not distinct from old.updated_at then
+    new.updated_at = now();
+  end if;
+  return new;
+end;
+$$ language plpgsql;
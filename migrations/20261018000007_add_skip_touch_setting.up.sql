@@ -0,0 +1,26 @@
+-- writes that aren't changes to the data, such as re-encrypting with a new
+-- key, set app.skip_touch for their transaction so the rows keep their
+-- updated_at and version
+create or replace function set_updated_at() returns trigger as $$
+begin
+  if current_setting('app.skip_touch', true) = 'on' then
+    return new;
+  end if;
+  if new.updated_at is not distinct from old.updated_at then
+    new.updated_at = now();
+  end if;
+  return new;
+end;
+$$ language plpgsql;
+
+create or replace function bump_version() returns trigger as $$
+begin
+  if current_setting('app.skip_touch', true) = 'on' then
+    return new;
+  end if;
+  if new.version is not distinct from old.version then
+    new.version = old.version + 1;
+  end if;
+  return new;
+end;
+$$ language plpgsql;
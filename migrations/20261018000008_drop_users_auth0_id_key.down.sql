@@ -0,0 +1,7 @@
+create unique index users_auth0_id_key on users (auth0_id) where deleted_at is null;
+
+set local app.skip_touch = 'on';
+
+update users set auth0_id_index = null where auth0_id_index = auth0_id;
+
+set local app.skip_touch = 'off';
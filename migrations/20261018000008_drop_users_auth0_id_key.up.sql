@@ -0,0 +1,12 @@
+-- auth0_id holds randomized ciphertext once encryption is enabled, so its
+-- unique index can't catch duplicates; auth0_id_index is the uniqueness key,
+-- holding the plaintext for rows that aren't encrypted yet until rotate-keys
+-- replaces it with the blind index
+set local app.skip_touch = 'on';
+
+update users set auth0_id_index = auth0_id
+  where auth0_id_index is null and auth0_id is not null and auth0_id not like 'enc:v%';
+
+set local app.skip_touch = 'off';
+
+drop index users_auth0_id_key;
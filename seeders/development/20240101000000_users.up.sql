@@ -1,3 +0,0 @@
-insert into users (user_id, role, first_name, last_name, auth0_id)
-  values
-    ('975aa9d0-7c3e-463d-a463-23ec4226f894', 'admin', 'Danny', 'Sullivan', 'google-oauth2|107121023659381840258');
@@ -1,3 +0,0 @@
-insert into groups (group_id, name)
-  values
-    ('799c9f9b-8368-4bf0-b28b-697f70ecdf36', 'Test Group');
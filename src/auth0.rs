@@ -9,6 +9,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use thiserror::Error;
 
+use crate::telemetry;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Auth0Identity {
     pub provider: String,
@@ -71,10 +73,11 @@ impl Auth0Client {
 
         let mut req = client
             .request(method, url)
+            .headers(telemetry::trace_headers())
             .header("authorization", format!("Bearer {}", access_token));
 
-        if body.is_some() {
-            req = req.json(&body.unwrap());
+        if let Some(body) = body {
+            req = req.json(&body);
         }
 
         req.send()
@@ -121,6 +124,7 @@ impl IAuth0Client for Auth0Client {
                 reqwest::Method::POST,
                 format!("{}{}", self.api_url, "/oauth/token"),
             )
+            .headers(telemetry::trace_headers())
             .form(&access_token_params);
 
         let access_token_res: serde_json::Value = access_token_req
@@ -1,16 +1,20 @@
+mod jwks;
+
+use std::sync::Arc;
+
 use anyhow::anyhow;
 use async_trait::async_trait;
 use derive_more::Display;
 use jsonwebtoken::{
-    decode, decode_header,
-    jwk::{AlgorithmParameters, JwkSet},
-    Algorithm, DecodingKey, Validation,
+    decode, decode_header, jwk::AlgorithmParameters, Algorithm, DecodingKey, Validation,
 };
 use mockall::predicate::*;
 use mockall::*;
 
 use serde::{Deserialize, Serialize};
 
+pub use self::jwks::JwksCache;
+
 #[derive(Debug, Display)]
 pub enum AuthError {
     #[display(fmt = "decode")]
@@ -34,16 +38,44 @@ pub struct Claims {
 #[automock]
 pub trait IAuthentication: Send + Sync {
     async fn validate_token(&self, token: String) -> Result<Claims, AuthError>;
+    // the number of signing keys available to validate tokens with
+    async fn check_keys(&self) -> Result<usize, AuthError>;
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone)]
 pub struct Authentication {
     pub audience: String,
     pub domain: String,
+    jwks: Arc<JwksCache>,
+}
+
+impl Authentication {
+    pub fn new(audience: String, domain: String) -> Authentication {
+        let jwks = JwksCache::new(format!("https://{}/.well-known/jwks.json", domain));
+        Authentication {
+            audience,
+            domain,
+            jwks: Arc::new(jwks),
+        }
+    }
+
+    pub fn with_jwks(mut self, jwks: JwksCache) -> Authentication {
+        self.jwks = Arc::new(jwks);
+        self
+    }
+
+    // loads the JWKS ahead of the first request
+    pub async fn warm_up(&self) -> Result<(), AuthError> {
+        self.jwks.refresh().await
+    }
 }
 
 #[async_trait]
 impl IAuthentication for Authentication {
+    async fn check_keys(&self) -> Result<usize, AuthError> {
+        self.jwks.ensure_loaded().await
+    }
+
     async fn validate_token(&self, token: String) -> Result<Claims, AuthError> {
         tracing::debug!("validating token");
 
@@ -55,20 +87,9 @@ impl IAuthentication for Authentication {
             .kid
             .ok_or_else(|| AuthError::NotFound("kid not found in token header".to_string()))?;
         tracing::debug!("found kid");
-        let jwks_endpoint = format!("https://{}/.well-known/jwks.json", self.domain);
-        tracing::debug!("{}", jwks_endpoint);
-        let jwks: JwkSet = reqwest::get(&jwks_endpoint)
-            .await
-            .map_err(|err| AuthError::RequestFailed(anyhow!(err)))?
-            .json()
-            .await
-            .map_err(|err| AuthError::RequestFailed(anyhow!(err)))?;
-        tracing::debug!("found jwks");
-        let jwk = jwks
-            .find(&kid)
-            .ok_or_else(|| AuthError::NotFound("No JWK found for kid".to_string()))?;
+        let jwk = self.jwks.get(&kid).await?;
         tracing::debug!("matched kid");
-        match jwk.clone().algorithm {
+        match jwk.algorithm {
             AlgorithmParameters::RSA(ref rsa) => {
                 let mut validation = Validation::new(Algorithm::RS256);
                 validation.set_audience(&[self.audience.as_str()]);
@@ -0,0 +1,218 @@
+#[path = "jwks_test.rs"]
+#[cfg(test)]
+mod jwks_test;
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use reqwest::header::{HeaderMap, CACHE_CONTROL};
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Instant;
+
+use super::AuthError;
+use crate::{metrics, telemetry};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(10 * 60);
+const MAX_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct CachedJwks {
+    jwks: JwkSet,
+    expires_at: Instant,
+}
+
+impl CachedJwks {
+    fn is_fresh(&self) -> bool {
+        Instant::now() < self.expires_at
+    }
+}
+
+// caches the JWKS served by the identity provider, refreshing it when the
+// Cache-Control lifetime runs out or when a token references an unknown kid
+pub struct JwksCache {
+    jwks_uri: String,
+    client: reqwest::Client,
+    default_ttl: Duration,
+    min_refetch_interval: Duration,
+    cached: RwLock<Option<CachedJwks>>,
+    // held for the duration of a fetch so concurrent misses share one request
+    last_fetch: Mutex<Option<Instant>>,
+}
+
+impl JwksCache {
+    pub fn new(jwks_uri: String) -> JwksCache {
+        JwksCache {
+            jwks_uri,
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            default_ttl: DEFAULT_TTL,
+            min_refetch_interval: DEFAULT_MIN_REFETCH_INTERVAL,
+            cached: RwLock::new(None),
+            last_fetch: Mutex::new(None),
+        }
+    }
+
+    pub fn with_default_ttl(mut self, default_ttl: Duration) -> JwksCache {
+        self.default_ttl = default_ttl;
+        self
+    }
+
+    pub fn with_min_refetch_interval(mut self, min_refetch_interval: Duration) -> JwksCache {
+        self.min_refetch_interval = min_refetch_interval;
+        self
+    }
+
+    pub async fn get(&self, kid: &str) -> Result<Jwk, AuthError> {
+        if let Some(jwk) = self.find_fresh(kid).await {
+            return Ok(jwk);
+        }
+
+        let mut last_fetch = self.last_fetch.lock().await;
+
+        // another request may have refreshed the keys while we were waiting
+        if let Some(jwk) = self.find_fresh(kid).await {
+            return Ok(jwk);
+        }
+
+        let rate_limited =
+            last_fetch.is_some_and(|last_fetch| last_fetch.elapsed() < self.min_refetch_interval);
+
+        if rate_limited {
+            tracing::debug!("jwks refetch rate limited");
+            return self
+                .find_stale(kid)
+                .await
+                .ok_or_else(|| AuthError::NotFound("No JWK found for kid".to_string()));
+        }
+
+        *last_fetch = Some(Instant::now());
+
+        match self.fetch().await {
+            Ok(()) => self
+                .find_stale(kid)
+                .await
+                .ok_or_else(|| AuthError::NotFound("No JWK found for kid".to_string())),
+            Err(err) => {
+                // keep serving the keys we already have if the provider is unavailable
+                if let Some(jwk) = self.find_stale(kid).await {
+                    tracing::warn!("jwks refresh failed, using cached keys: {}", err);
+                    return Ok(jwk);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    pub async fn refresh(&self) -> Result<(), AuthError> {
+        let mut last_fetch = self.last_fetch.lock().await;
+        *last_fetch = Some(Instant::now());
+        self.fetch().await
+    }
+
+    // the number of cached keys, fetching them first if none were ever loaded;
+    // stale keys count since tokens are still validated against them
+    pub async fn ensure_loaded(&self) -> Result<usize, AuthError> {
+        if let Some(count) = self.loaded_keys().await {
+            return Ok(count);
+        }
+
+        let mut last_fetch = self.last_fetch.lock().await;
+
+        if let Some(count) = self.loaded_keys().await {
+            return Ok(count);
+        }
+
+        let rate_limited =
+            last_fetch.is_some_and(|last_fetch| last_fetch.elapsed() < self.min_refetch_interval);
+
+        if rate_limited {
+            return Err(AuthError::NotFound("No JWKS loaded".to_string()));
+        }
+
+        *last_fetch = Some(Instant::now());
+        self.fetch().await?;
+
+        Ok(self.loaded_keys().await.unwrap_or_default())
+    }
+
+    async fn loaded_keys(&self) -> Option<usize> {
+        let cached = self.cached.read().await;
+        cached.as_ref().map(|cached| cached.jwks.keys.len())
+    }
+
+    async fn find_fresh(&self, kid: &str) -> Option<Jwk> {
+        let cached = self.cached.read().await;
+        cached
+            .as_ref()
+            .filter(|cached| cached.is_fresh())
+            .and_then(|cached| cached.jwks.find(kid).cloned())
+    }
+
+    async fn find_stale(&self, kid: &str) -> Option<Jwk> {
+        let cached = self.cached.read().await;
+        cached
+            .as_ref()
+            .and_then(|cached| cached.jwks.find(kid).cloned())
+    }
+
+    async fn fetch(&self) -> Result<(), AuthError> {
+        let started = std::time::Instant::now();
+        let res = self.fetch_jwks().await;
+        metrics::get().observe_jwks_fetch(res.is_ok(), started.elapsed());
+        res
+    }
+
+    async fn fetch_jwks(&self) -> Result<(), AuthError> {
+        tracing::debug!("fetching jwks from {}", self.jwks_uri);
+
+        let res = self
+            .client
+            .get(&self.jwks_uri)
+            .headers(telemetry::trace_headers())
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|err| AuthError::RequestFailed(anyhow!(err)))?;
+
+        let ttl = ttl_from_headers(res.headers()).unwrap_or(self.default_ttl);
+
+        let jwks: JwkSet = res
+            .json()
+            .await
+            .map_err(|err| AuthError::RequestFailed(anyhow!(err)))?;
+
+        tracing::debug!("fetched {} jwks, caching for {:?}", jwks.keys.len(), ttl);
+
+        *self.cached.write().await = Some(CachedJwks {
+            jwks,
+            expires_at: Instant::now() + ttl,
+        });
+
+        Ok(())
+    }
+}
+
+fn ttl_from_headers(headers: &HeaderMap) -> Option<Duration> {
+    let cache_control = headers.get(CACHE_CONTROL)?.to_str().ok()?;
+
+    let mut ttl = None;
+
+    // directive names are case-insensitive
+    for directive in cache_control
+        .split(',')
+        .map(|directive| directive.trim().to_ascii_lowercase())
+    {
+        if directive == "no-cache" || directive == "no-store" {
+            return Some(Duration::ZERO);
+        }
+        if let Some(max_age) = directive.strip_prefix("max-age=") {
+            ttl = max_age.parse::<u64>().ok().map(Duration::from_secs);
+        }
+    }
+
+    ttl.map(|ttl| ttl.min(MAX_TTL))
+}
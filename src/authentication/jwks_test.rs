@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use axum::{
+        extract::State,
+        http::{header, StatusCode},
+        response::IntoResponse,
+        routing::get,
+        Json, Router,
+    };
+
+    use crate::authentication::{AuthError, JwksCache};
+
+    #[derive(Clone)]
+    struct StubJwks {
+        hits: Arc<AtomicUsize>,
+        failing: Arc<AtomicBool>,
+        kids: Arc<Mutex<Vec<String>>>,
+        cache_control: &'static str,
+    }
+
+    async fn jwks_handler(State(stub): State<StubJwks>) -> impl IntoResponse {
+        stub.hits.fetch_add(1, Ordering::SeqCst);
+
+        if stub.failing.load(Ordering::SeqCst) {
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+
+        let keys: Vec<serde_json::Value> = stub
+            .kids
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|kid| {
+                serde_json::json!({
+                    "kty": "RSA",
+                    "use": "sig",
+                    "alg": "RS256",
+                    "kid": kid,
+                    "n": "n",
+                    "e": "AQAB",
+                })
+            })
+            .collect();
+
+        (
+            [(header::CACHE_CONTROL, stub.cache_control)],
+            Json(serde_json::json!({ "keys": keys })),
+        )
+            .into_response()
+    }
+
+    async fn start_stub(cache_control: &'static str, kids: Vec<&str>) -> (String, StubJwks) {
+        let stub = StubJwks {
+            hits: Arc::new(AtomicUsize::new(0)),
+            failing: Arc::new(AtomicBool::new(false)),
+            kids: Arc::new(Mutex::new(kids.iter().map(|kid| kid.to_string()).collect())),
+            cache_control,
+        };
+
+        let app = Router::new()
+            .route("/.well-known/jwks.json", get(jwks_handler))
+            .with_state(stub.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}/.well-known/jwks.json", addr), stub)
+    }
+
+    #[tokio::test]
+    async fn test_get_caches_keys() {
+        let (jwks_uri, stub) = start_stub("public, max-age=600", vec!["kid_1"]).await;
+
+        let jwks = JwksCache::new(jwks_uri);
+
+        jwks.get("kid_1").await.unwrap();
+        jwks.get("kid_1").await.unwrap();
+
+        assert_eq!(stub.hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_refetches_on_unknown_kid() {
+        let (jwks_uri, stub) = start_stub("public, max-age=600", vec!["kid_1"]).await;
+
+        let jwks = JwksCache::new(jwks_uri).with_min_refetch_interval(Duration::ZERO);
+
+        jwks.get("kid_1").await.unwrap();
+
+        stub.kids.lock().unwrap().push("kid_2".to_owned());
+
+        let jwk = jwks.get("kid_2").await.unwrap();
+
+        assert_eq!(jwk.common.key_id, Some("kid_2".to_owned()));
+        assert_eq!(stub.hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_rate_limits_unknown_kid() {
+        let (jwks_uri, stub) = start_stub("public, max-age=600", vec!["kid_1"]).await;
+
+        let jwks = JwksCache::new(jwks_uri).with_min_refetch_interval(Duration::from_secs(60));
+
+        jwks.get("kid_1").await.unwrap();
+
+        let first = jwks.get("kid_unknown").await;
+        let second = jwks.get("kid_unknown").await;
+
+        assert!(matches!(first, Err(AuthError::NotFound(_))));
+        assert!(matches!(second, Err(AuthError::NotFound(_))));
+        assert_eq!(stub.hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_honours_cache_control() {
+        let (jwks_uri, stub) = start_stub("no-cache", vec!["kid_1"]).await;
+
+        let jwks = JwksCache::new(jwks_uri).with_min_refetch_interval(Duration::ZERO);
+
+        jwks.get("kid_1").await.unwrap();
+        jwks.get("kid_1").await.unwrap();
+
+        assert_eq!(stub.hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_honours_mixed_case_cache_control() {
+        let (jwks_uri, stub) = start_stub("Public,  Max-Age=600 ", vec!["kid_1"]).await;
+
+        // without a usable max-age every get would refetch
+        let jwks = JwksCache::new(jwks_uri)
+            .with_default_ttl(Duration::ZERO)
+            .with_min_refetch_interval(Duration::ZERO);
+
+        jwks.get("kid_1").await.unwrap();
+        jwks.get("kid_1").await.unwrap();
+
+        assert_eq!(stub.hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_serves_stale_keys_when_fetch_fails() {
+        let (jwks_uri, stub) = start_stub("max-age=0", vec!["kid_1"]).await;
+
+        let jwks = JwksCache::new(jwks_uri).with_min_refetch_interval(Duration::ZERO);
+
+        jwks.refresh().await.unwrap();
+
+        stub.failing.store(true, Ordering::SeqCst);
+
+        let jwk = jwks.get("kid_1").await.unwrap();
+
+        assert_eq!(jwk.common.key_id, Some("kid_1".to_owned()));
+        assert_eq!(stub.hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_fails_without_cached_keys() {
+        let (jwks_uri, stub) = start_stub("max-age=600", vec!["kid_1"]).await;
+
+        stub.failing.store(true, Ordering::SeqCst);
+
+        let jwks = JwksCache::new(jwks_uri);
+
+        let res = jwks.get("kid_1").await;
+
+        assert!(matches!(res, Err(AuthError::RequestFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_loaded_fetches_once() {
+        let (jwks_uri, stub) = start_stub("max-age=0", vec!["kid_1", "kid_2"]).await;
+
+        let jwks = JwksCache::new(jwks_uri).with_min_refetch_interval(Duration::ZERO);
+
+        assert_eq!(jwks.ensure_loaded().await.unwrap(), 2);
+
+        stub.failing.store(true, Ordering::SeqCst);
+
+        assert_eq!(jwks.ensure_loaded().await.unwrap(), 2);
+        assert_eq!(stub.hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::metrics;
+
+use super::{AuthorizationError, GroupUser, IAuthorization, User};
+
+// wraps an authorization engine and counts the checks it denies, labelled by
+// the check that was made
+pub struct MeteredAuthorization {
+    inner: Arc<dyn IAuthorization>,
+}
+
+impl MeteredAuthorization {
+    pub fn new(inner: Arc<dyn IAuthorization>) -> MeteredAuthorization {
+        MeteredAuthorization { inner }
+    }
+}
+
+fn observe(check: &str, res: Result<(), AuthorizationError>) -> Result<(), AuthorizationError> {
+    if let Err(AuthorizationError::NotAuthorized()) = res {
+        metrics::get().observe_denial(check);
+    }
+    res
+}
+
+impl IAuthorization for MeteredAuthorization {
+    fn can_get_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError> {
+        observe("can_get_user", self.inner.can_get_user(actor, resource_id))
+    }
+
+    fn can_modify_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError> {
+        observe(
+            "can_modify_user",
+            self.inner.can_modify_user(actor, resource_id),
+        )
+    }
+
+    fn can_modify_user_field(
+        &self,
+        actor: User,
+        resource: User,
+        field: String,
+    ) -> Result<(), AuthorizationError> {
+        observe(
+            "can_modify_user_field",
+            self.inner.can_modify_user_field(actor, resource, field),
+        )
+    }
+
+    fn can_delete_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError> {
+        observe(
+            "can_delete_user",
+            self.inner.can_delete_user(actor, resource_id),
+        )
+    }
+
+    fn can_restore_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError> {
+        observe(
+            "can_restore_user",
+            self.inner.can_restore_user(actor, resource_id),
+        )
+    }
+
+    fn can_list_users(&self, actor: User) -> Result<(), AuthorizationError> {
+        observe("can_list_users", self.inner.can_list_users(actor))
+    }
+
+    fn can_list_groups(&self, actor: User) -> Result<(), AuthorizationError> {
+        observe("can_list_groups", self.inner.can_list_groups(actor))
+    }
+
+    fn can_get_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        observe("can_get_group", self.inner.can_get_group(actor, membership))
+    }
+
+    fn can_create_group(&self, actor: User) -> Result<(), AuthorizationError> {
+        observe("can_create_group", self.inner.can_create_group(actor))
+    }
+
+    fn can_manage_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        observe(
+            "can_manage_group",
+            self.inner.can_manage_group(actor, membership),
+        )
+    }
+
+    fn can_delete_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        observe(
+            "can_delete_group",
+            self.inner.can_delete_group(actor, membership),
+        )
+    }
+
+    fn can_list_group_users(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        observe(
+            "can_list_group_users",
+            self.inner.can_list_group_users(actor, membership),
+        )
+    }
+
+    fn can_add_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        role: String,
+    ) -> Result<(), AuthorizationError> {
+        observe(
+            "can_add_member",
+            self.inner.can_add_member(actor, membership, role),
+        )
+    }
+
+    fn can_modify_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        member: GroupUser,
+        role: String,
+    ) -> Result<(), AuthorizationError> {
+        observe(
+            "can_modify_member",
+            self.inner
+                .can_modify_member(actor, membership, member, role),
+        )
+    }
+
+    fn can_remove_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        member: GroupUser,
+    ) -> Result<(), AuthorizationError> {
+        observe(
+            "can_remove_member",
+            self.inner.can_remove_member(actor, membership, member),
+        )
+    }
+
+    fn can_list_user_groups(&self, actor: User, user_id: Uuid) -> Result<(), AuthorizationError> {
+        observe(
+            "can_list_user_groups",
+            self.inner.can_list_user_groups(actor, user_id),
+        )
+    }
+}
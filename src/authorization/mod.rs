@@ -1,29 +1,100 @@
+pub mod metered;
+pub mod oso;
+
 use mockall::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use derive_more::Display;
 
+pub const GROUP_ROLE_OWNER: &str = "owner";
+pub const GROUP_ROLE_ADMIN: &str = "admin";
+pub const GROUP_ROLE_MEMBER: &str = "member";
+
+pub const GROUP_ROLES: [&str; 3] = [GROUP_ROLE_OWNER, GROUP_ROLE_ADMIN, GROUP_ROLE_MEMBER];
+
+// fields users are allowed to change on their own record
+pub const USER_SELF_MODIFIABLE_FIELDS: [&str; 2] = ["first_name", "last_name"];
+
 #[derive(Clone)]
 pub struct User {
     pub user_id: Uuid,
     pub role: String,
 }
 
+// a user's membership in a group, along with their role within that group
+#[derive(Clone)]
+pub struct GroupUser {
+    pub group_id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+}
+
 // define authorization error struct
 #[derive(Debug, Display)]
 pub enum AuthorizationError {
     #[display(fmt = "not authorized")]
     NotAuthorized(),
+    #[display(fmt = "authorization error")]
+    Error(anyhow::Error),
 }
 
 #[automock]
 pub trait IAuthorization: Send + Sync {
     fn can_get_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError>;
-    // fn can_modify_user(&self, actor: User, resource_id: Uuid) -> Result<bool, AuthorizationError>;
-    // fn can_delete_user(&self, actor: User, resource_id: Uuid) -> Result<bool, AuthorizationError>;
+    fn can_modify_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError>;
+    fn can_modify_user_field(
+        &self,
+        actor: User,
+        resource: User,
+        field: String,
+    ) -> Result<(), AuthorizationError>;
+    fn can_delete_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError>;
+    fn can_restore_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError>;
     fn can_list_users(&self, actor: User) -> Result<(), AuthorizationError>;
     // fn can_create_user(&self, actor: User) -> Result<bool, AuthorizationError>;
+    fn can_list_groups(&self, actor: User) -> Result<(), AuthorizationError>;
+    fn can_get_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError>;
+    fn can_create_group(&self, actor: User) -> Result<(), AuthorizationError>;
+    fn can_manage_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError>;
+    fn can_delete_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError>;
+    fn can_list_group_users(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError>;
+    fn can_add_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        role: String,
+    ) -> Result<(), AuthorizationError>;
+    fn can_modify_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        member: GroupUser,
+        role: String,
+    ) -> Result<(), AuthorizationError>;
+    fn can_remove_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        member: GroupUser,
+    ) -> Result<(), AuthorizationError>;
+    fn can_list_user_groups(&self, actor: User, user_id: Uuid) -> Result<(), AuthorizationError>;
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -33,6 +104,23 @@ impl Authorization {
     fn is_user_admin(&self, actor: User) -> bool {
         actor.role == "admin"
     }
+
+    fn is_group_member(&self, membership: &Option<GroupUser>) -> bool {
+        membership.is_some()
+    }
+
+    fn is_group_owner(&self, membership: &Option<GroupUser>) -> bool {
+        membership
+            .as_ref()
+            .is_some_and(|membership| membership.role == GROUP_ROLE_OWNER)
+    }
+
+    // owners are implicitly group admins
+    fn is_group_admin(&self, membership: &Option<GroupUser>) -> bool {
+        membership.as_ref().is_some_and(|membership| {
+            membership.role == GROUP_ROLE_OWNER || membership.role == GROUP_ROLE_ADMIN
+        })
+    }
 }
 
 impl IAuthorization for Authorization {
@@ -43,10 +131,163 @@ impl IAuthorization for Authorization {
         Err(AuthorizationError::NotAuthorized())
     }
 
+    fn can_modify_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor.clone()) || actor.user_id == resource_id {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_modify_user_field(
+        &self,
+        actor: User,
+        resource: User,
+        field: String,
+    ) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor.clone()) {
+            return Ok(());
+        }
+        if actor.user_id == resource.user_id
+            && USER_SELF_MODIFIABLE_FIELDS.contains(&field.as_str())
+        {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_delete_user(&self, actor: User, resource_id: Uuid) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor.clone()) || actor.user_id == resource_id {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    // deleted users can't act, so only admins can bring them back
+    fn can_restore_user(&self, actor: User, _resource_id: Uuid) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
     fn can_list_users(&self, actor: User) -> Result<(), AuthorizationError> {
         if self.is_user_admin(actor) {
             return Ok(());
         }
         Err(AuthorizationError::NotAuthorized())
     }
+
+    fn can_list_groups(&self, actor: User) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_get_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) || self.is_group_member(&membership) {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_create_group(&self, _actor: User) -> Result<(), AuthorizationError> {
+        Ok(())
+    }
+
+    fn can_manage_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) || self.is_group_admin(&membership) {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_delete_group(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) || self.is_group_owner(&membership) {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_list_group_users(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) || self.is_group_member(&membership) {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_add_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        role: String,
+    ) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) || self.is_group_owner(&membership) {
+            return Ok(());
+        }
+        if self.is_group_admin(&membership) && role != GROUP_ROLE_OWNER {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_modify_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        member: GroupUser,
+        role: String,
+    ) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor) || self.is_group_owner(&membership) {
+            return Ok(());
+        }
+        if self.is_group_admin(&membership)
+            && member.role != GROUP_ROLE_OWNER
+            && role != GROUP_ROLE_OWNER
+        {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_remove_member(
+        &self,
+        actor: User,
+        membership: Option<GroupUser>,
+        member: GroupUser,
+    ) -> Result<(), AuthorizationError> {
+        if actor.user_id == member.user_id
+            || self.is_user_admin(actor)
+            || self.is_group_owner(&membership)
+        {
+            return Ok(());
+        }
+        if self.is_group_admin(&membership) && member.role != GROUP_ROLE_OWNER {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
+
+    fn can_list_user_groups(&self, actor: User, user_id: Uuid) -> Result<(), AuthorizationError> {
+        if self.is_user_admin(actor.clone()) || actor.user_id == user_id {
+            return Ok(());
+        }
+        Err(AuthorizationError::NotAuthorized())
+    }
 }
@@ -2,19 +2,67 @@
 #[cfg(test)]
 mod oso_test;
 
-use super::AuthorizationClient;
-use super::AuthorizationError;
+use super::{AuthorizationError, IAuthorization};
 
 use anyhow::anyhow;
 use oso;
-use oso::PolarClass;
+use oso::{PolarClass, ToPolar, ToPolarList};
+use uuid::Uuid;
+
+// policies compiled into the binary, used when no policy files are configured
+const DEFAULT_POLICIES: [&str; 2] = [include_str!("user.polar"), include_str!("group.polar")];
 
 #[derive(Clone, PolarClass)]
 pub struct User {
     #[polar(attribute)]
-    user_id: String,
+    pub user_id: String,
     #[polar(attribute)]
-    role: String,
+    pub role: String,
+}
+
+// a group as seen by the actor, role is empty when the actor is not a member
+#[derive(Clone, PolarClass)]
+pub struct Group {
+    #[polar(attribute)]
+    pub role: String,
+}
+
+// another user's membership in a group the actor is acting on
+#[derive(Clone, PolarClass)]
+pub struct GroupUser {
+    #[polar(attribute)]
+    pub user_id: String,
+    #[polar(attribute)]
+    pub role: String,
+    #[polar(attribute)]
+    pub group: Group,
+}
+
+impl From<super::User> for User {
+    fn from(user: super::User) -> Self {
+        User {
+            user_id: user.user_id.to_string(),
+            role: user.role,
+        }
+    }
+}
+
+impl From<Option<super::GroupUser>> for Group {
+    fn from(membership: Option<super::GroupUser>) -> Self {
+        Group {
+            role: membership
+                .map(|membership| membership.role)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// users referenced only by id carry no role
+fn user_resource(user_id: Uuid) -> User {
+    User {
+        user_id: user_id.to_string(),
+        role: "".to_owned(),
+    }
 }
 
 pub struct OsoAuthorizationClient {
@@ -25,25 +73,208 @@ impl OsoAuthorizationClient {
     pub fn new(oso: oso::Oso) -> OsoAuthorizationClient {
         OsoAuthorizationClient { oso }
     }
-}
 
-impl AuthorizationClient<User> for OsoAuthorizationClient {
-    fn allow_user_action_field(
-        &self,
-        actor: User,
-        action: String,
-        resource: User,
-        field: String,
-    ) -> Result<bool, AuthorizationError> {
+    pub fn from_default_policies() -> Result<OsoAuthorizationClient, AuthorizationError> {
+        let mut oso = Self::build_oso()?;
+        oso.load_str(DEFAULT_POLICIES.join("\n").as_str())
+            .map_err(|err| AuthorizationError::Error(anyhow!(err)))?;
+        Ok(OsoAuthorizationClient::new(oso))
+    }
+
+    pub fn from_files(files: Vec<String>) -> Result<OsoAuthorizationClient, AuthorizationError> {
+        let mut oso = Self::build_oso()?;
+        oso.load_files(files)
+            .map_err(|err| AuthorizationError::Error(anyhow!(err)))?;
+        Ok(OsoAuthorizationClient::new(oso))
+    }
+
+    fn build_oso() -> Result<oso::Oso, AuthorizationError> {
+        let mut oso = oso::Oso::new();
+        oso.register_class(User::get_polar_class())
+            .map_err(|err| AuthorizationError::Error(anyhow!(err)))?;
+        oso.register_class(Group::get_polar_class())
+            .map_err(|err| AuthorizationError::Error(anyhow!(err)))?;
+        oso.register_class(GroupUser::get_polar_class())
+            .map_err(|err| AuthorizationError::Error(anyhow!(err)))?;
+        Ok(oso)
+    }
+
+    fn query(&self, rule: &str, args: impl ToPolarList) -> Result<(), AuthorizationError> {
         let mut query = self
             .oso
-            .query_rule("allow_field", (actor, action, resource, field))
+            .query_rule(rule, args)
             .map_err(|err| AuthorizationError::Error(anyhow!(err)))?;
 
         match query.next() {
-            Some(Ok(_)) => Ok(true),
+            Some(Ok(_)) => Ok(()),
             Some(Err(e)) => Err(AuthorizationError::Error(anyhow!(e))),
-            None => Ok(false),
+            None => Err(AuthorizationError::NotAuthorized()),
         }
     }
+
+    fn allow(
+        &self,
+        actor: super::User,
+        action: &str,
+        resource: impl ToPolar,
+    ) -> Result<(), AuthorizationError> {
+        self.query("allow", (User::from(actor), action, resource))
+    }
+
+    fn allow_field(
+        &self,
+        actor: super::User,
+        action: &str,
+        resource: impl ToPolar,
+        field: String,
+    ) -> Result<(), AuthorizationError> {
+        self.query("allow_field", (User::from(actor), action, resource, field))
+    }
+
+    pub fn allow_user_action_field(
+        &self,
+        actor: User,
+        action: String,
+        resource: User,
+        field: String,
+    ) -> Result<bool, AuthorizationError> {
+        match self.query("allow_field", (actor, action, resource, field)) {
+            Ok(()) => Ok(true),
+            Err(AuthorizationError::NotAuthorized()) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl IAuthorization for OsoAuthorizationClient {
+    fn can_get_user(
+        &self,
+        actor: super::User,
+        resource_id: Uuid,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "read", user_resource(resource_id))
+    }
+
+    fn can_modify_user(
+        &self,
+        actor: super::User,
+        resource_id: Uuid,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "update", user_resource(resource_id))
+    }
+
+    fn can_modify_user_field(
+        &self,
+        actor: super::User,
+        resource: super::User,
+        field: String,
+    ) -> Result<(), AuthorizationError> {
+        self.allow_field(actor, "update", User::from(resource), field)
+    }
+
+    fn can_delete_user(
+        &self,
+        actor: super::User,
+        resource_id: Uuid,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "delete", user_resource(resource_id))
+    }
+
+    fn can_restore_user(
+        &self,
+        actor: super::User,
+        resource_id: Uuid,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "restore", user_resource(resource_id))
+    }
+
+    fn can_list_users(&self, actor: super::User) -> Result<(), AuthorizationError> {
+        self.allow(actor, "list", "users")
+    }
+
+    fn can_list_groups(&self, actor: super::User) -> Result<(), AuthorizationError> {
+        self.allow(actor, "list", "groups")
+    }
+
+    fn can_get_group(
+        &self,
+        actor: super::User,
+        membership: Option<super::GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "read", Group::from(membership))
+    }
+
+    fn can_create_group(&self, actor: super::User) -> Result<(), AuthorizationError> {
+        self.allow(actor, "create", "groups")
+    }
+
+    fn can_manage_group(
+        &self,
+        actor: super::User,
+        membership: Option<super::GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "update", Group::from(membership))
+    }
+
+    fn can_delete_group(
+        &self,
+        actor: super::User,
+        membership: Option<super::GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "delete", Group::from(membership))
+    }
+
+    fn can_list_group_users(
+        &self,
+        actor: super::User,
+        membership: Option<super::GroupUser>,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "list_users", Group::from(membership))
+    }
+
+    fn can_add_member(
+        &self,
+        actor: super::User,
+        membership: Option<super::GroupUser>,
+        role: String,
+    ) -> Result<(), AuthorizationError> {
+        self.allow_field(actor, "add_user", Group::from(membership), role)
+    }
+
+    fn can_modify_member(
+        &self,
+        actor: super::User,
+        membership: Option<super::GroupUser>,
+        member: super::GroupUser,
+        role: String,
+    ) -> Result<(), AuthorizationError> {
+        let member = GroupUser {
+            user_id: member.user_id.to_string(),
+            role: member.role,
+            group: Group::from(membership),
+        };
+        self.allow_field(actor, "update", member, role)
+    }
+
+    fn can_remove_member(
+        &self,
+        actor: super::User,
+        membership: Option<super::GroupUser>,
+        member: super::GroupUser,
+    ) -> Result<(), AuthorizationError> {
+        let member = GroupUser {
+            user_id: member.user_id.to_string(),
+            role: member.role,
+            group: Group::from(membership),
+        };
+        self.allow(actor, "delete", member)
+    }
+
+    fn can_list_user_groups(
+        &self,
+        actor: super::User,
+        user_id: Uuid,
+    ) -> Result<(), AuthorizationError> {
+        self.allow(actor, "list_groups", user_resource(user_id))
+    }
 }
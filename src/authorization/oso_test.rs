@@ -1,21 +1,37 @@
 #[cfg(test)]
 mod test_authorization {
-    use anyhow::anyhow;
-    use oso;
     use uuid::Uuid;
 
     use crate::authorization;
     use crate::authorization::oso::OsoAuthorizationClient;
-    use crate::authorization::AuthorizationClient;
+    use crate::authorization::IAuthorization;
+
+    fn get_oso_authz_client() -> OsoAuthorizationClient {
+        OsoAuthorizationClient::from_files(vec![
+            "src/authorization/user.polar".to_owned(),
+            "src/authorization/group.polar".to_owned(),
+        ])
+        .unwrap()
+    }
+
+    fn get_actor(role: &str) -> authorization::User {
+        authorization::User {
+            user_id: Uuid::new_v4(),
+            role: role.to_owned(),
+        }
+    }
+
+    fn get_membership(actor: &authorization::User, role: &str) -> Option<authorization::GroupUser> {
+        Some(authorization::GroupUser {
+            group_id: Uuid::new_v4(),
+            user_id: actor.user_id,
+            role: role.to_owned(),
+        })
+    }
 
     #[tokio::test]
     async fn test_allow_user_action_field() -> Result<(), authorization::AuthorizationError> {
-        let mut oso_client = oso::Oso::new();
-        oso_client
-            .load_files(vec!["src/authorization/user.polar"])
-            .map_err(|err| authorization::AuthorizationError::Error(anyhow!(err)))?;
-
-        let oso_authz_client = OsoAuthorizationClient::new(oso_client);
+        let oso_authz_client = get_oso_authz_client();
 
         let user_id = Uuid::new_v4();
 
@@ -40,4 +56,149 @@ mod test_authorization {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_default_policies_load() {
+        OsoAuthorizationClient::from_default_policies().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_can_get_user() {
+        let oso_authz_client = get_oso_authz_client();
+
+        let actor = get_actor("user");
+
+        assert!(oso_authz_client
+            .can_get_user(actor.clone(), actor.user_id)
+            .is_ok());
+        assert!(oso_authz_client
+            .can_get_user(actor.clone(), Uuid::new_v4())
+            .is_err());
+        assert!(oso_authz_client
+            .can_get_user(get_actor("admin"), actor.user_id)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_can_restore_user() {
+        let oso_authz_client = get_oso_authz_client();
+
+        let actor = get_actor("user");
+
+        assert!(oso_authz_client
+            .can_restore_user(actor.clone(), actor.user_id)
+            .is_err());
+        assert!(oso_authz_client
+            .can_restore_user(get_actor("admin"), actor.user_id)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_can_list_users() {
+        let oso_authz_client = get_oso_authz_client();
+
+        assert!(oso_authz_client.can_list_users(get_actor("user")).is_err());
+        assert!(oso_authz_client.can_list_users(get_actor("admin")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_can_manage_group() {
+        let oso_authz_client = get_oso_authz_client();
+
+        let actor = get_actor("user");
+
+        assert!(oso_authz_client
+            .can_manage_group(actor.clone(), get_membership(&actor, "owner"))
+            .is_ok());
+        assert!(oso_authz_client
+            .can_manage_group(actor.clone(), get_membership(&actor, "admin"))
+            .is_ok());
+        assert!(oso_authz_client
+            .can_manage_group(actor.clone(), get_membership(&actor, "member"))
+            .is_err());
+        assert!(oso_authz_client
+            .can_manage_group(actor.clone(), None)
+            .is_err());
+        assert!(oso_authz_client
+            .can_manage_group(get_actor("admin"), None)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_can_add_member() {
+        let oso_authz_client = get_oso_authz_client();
+
+        let actor = get_actor("user");
+
+        assert!(oso_authz_client
+            .can_add_member(
+                actor.clone(),
+                get_membership(&actor, "owner"),
+                "owner".to_owned()
+            )
+            .is_ok());
+        assert!(oso_authz_client
+            .can_add_member(
+                actor.clone(),
+                get_membership(&actor, "admin"),
+                "member".to_owned()
+            )
+            .is_ok());
+        assert!(oso_authz_client
+            .can_add_member(
+                actor.clone(),
+                get_membership(&actor, "admin"),
+                "owner".to_owned()
+            )
+            .is_err());
+        assert!(oso_authz_client
+            .can_add_member(
+                actor.clone(),
+                get_membership(&actor, "member"),
+                "member".to_owned()
+            )
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_can_remove_member() {
+        let oso_authz_client = get_oso_authz_client();
+
+        let actor = get_actor("user");
+        let other = get_actor("user");
+
+        let owner = get_membership(&other, "owner").unwrap();
+        let member = get_membership(&other, "member").unwrap();
+        let own = get_membership(&actor, "member").unwrap();
+
+        assert!(oso_authz_client
+            .can_remove_member(actor.clone(), Some(own.clone()), own.clone())
+            .is_ok());
+        assert!(oso_authz_client
+            .can_remove_member(actor.clone(), get_membership(&actor, "admin"), member)
+            .is_ok());
+        assert!(oso_authz_client
+            .can_remove_member(actor.clone(), get_membership(&actor, "admin"), owner)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_can_modify_user_field() {
+        let oso_authz_client = get_oso_authz_client();
+
+        let actor = get_actor("user");
+
+        assert!(oso_authz_client
+            .can_modify_user_field(actor.clone(), actor.clone(), "first_name".to_owned())
+            .is_ok());
+        assert!(oso_authz_client
+            .can_modify_user_field(actor.clone(), actor.clone(), "role".to_owned())
+            .is_err());
+        assert!(oso_authz_client
+            .can_modify_user_field(actor.clone(), get_actor("user"), "first_name".to_owned())
+            .is_err());
+        assert!(oso_authz_client
+            .can_modify_user_field(get_actor("admin"), actor.clone(), "role".to_owned())
+            .is_ok());
+    }
 }
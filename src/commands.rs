@@ -0,0 +1,177 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+use clap::Subcommand;
+use sea_orm::DatabaseConnection;
+
+use crate::config::CommandConfig;
+use crate::seed::{Profile, SeedData};
+use crate::{database, encryption, key_rotation, migrations, telemetry};
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Apply, revert or list the migrations embedded in the binary
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Load seed data, updating rows that were seeded before
+    Seed {
+        /// Which of the files in seeders/ to load; there is no default so
+        /// seeding never picks the development admin by accident
+        #[arg(
+            long,
+            value_enum,
+            env = "SEED_PROFILE",
+            required_unless_present = "file"
+        )]
+        profile: Option<Profile>,
+        /// Load this file instead of the profile's
+        #[arg(long, conflicts_with = "profile")]
+        file: Option<PathBuf>,
+        /// Also generate this many fake users
+        #[arg(long, default_value_t = 0)]
+        fake_users: usize,
+        /// Also generate this many fake groups
+        #[arg(long, default_value_t = 0)]
+        fake_groups: usize,
+        /// How many of the fake users to add to each fake group
+        #[arg(long, default_value_t = 0)]
+        fake_members_per_group: usize,
+    },
+    /// Re-encrypt personal data with the first key in ENCRYPTION_KEY
+    RotateKeys {
+        /// How many users to re-encrypt per transaction
+        #[arg(long, default_value_t = 500)]
+        batch_size: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateAction {
+    /// Apply every pending migration
+    Up,
+    /// Revert the most recently applied migrations
+    Down {
+        /// How many migrations to revert
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// List the migrations and whether they are applied
+    Status {
+        /// Fail when an applied migration was changed or isn't known to
+        /// this binary
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+pub async fn run(config: CommandConfig, command: Command) -> anyhow::Result<()> {
+    let telemetry = telemetry::init(
+        config.telemetry.otlp_endpoint.as_deref(),
+        &config.telemetry.service_name,
+        config.telemetry.log_format,
+    )?;
+
+    if let Some(keyring) = config.encryption.keyring.clone() {
+        encryption::init(keyring);
+    }
+
+    let conn = sea_orm::Database::connect(database::connect_options(&config.database)?)
+        .await
+        .map_err(|err| anyhow!(err))?;
+
+    let res = match command {
+        Command::Migrate { action } => migrate(&conn, action).await,
+        Command::Seed {
+            profile,
+            file,
+            fake_users,
+            fake_groups,
+            fake_members_per_group,
+        } => {
+            seed(
+                &conn,
+                profile,
+                file,
+                fake_users,
+                fake_groups,
+                fake_members_per_group,
+            )
+            .await
+        }
+        Command::RotateKeys { batch_size } => rotate_keys(&conn, batch_size).await,
+    };
+
+    conn.close().await.map_err(|err| anyhow!(err))?;
+    telemetry.shutdown();
+
+    res
+}
+
+async fn migrate(conn: &DatabaseConnection, action: MigrateAction) -> anyhow::Result<()> {
+    match action {
+        MigrateAction::Up => {
+            let applied = migrations::up(conn).await?;
+            tracing::info!("applied {} migrations", applied.len());
+        }
+        MigrateAction::Down { steps } => {
+            let reverted = migrations::down(conn, steps).await?;
+            tracing::info!("reverted {} migrations", reverted.len());
+        }
+        MigrateAction::Status { strict } => {
+            let statuses = migrations::status(conn).await?;
+
+            for status in &statuses {
+                println!("{status}");
+            }
+
+            if strict {
+                migrations::verify_statuses(&statuses)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn seed(
+    conn: &DatabaseConnection,
+    profile: Option<Profile>,
+    file: Option<PathBuf>,
+    fake_users: usize,
+    fake_groups: usize,
+    fake_members_per_group: usize,
+) -> anyhow::Result<()> {
+    let mut data = match (file, profile) {
+        (Some(file), _) => SeedData::parse(
+            &std::fs::read_to_string(&file)
+                .with_context(|| format!("failed to read {}", file.display()))?,
+        )?,
+        (None, Some(profile)) => SeedData::parse(profile.source())?,
+        (None, None) => return Err(anyhow!("--profile or --file is required")),
+    };
+    data.extend(crate::seed::fake(
+        fake_users,
+        fake_groups,
+        fake_members_per_group,
+    )?);
+
+    crate::seed::seed(conn, &data).await?;
+
+    Ok(())
+}
+
+async fn rotate_keys(conn: &DatabaseConnection, batch_size: u64) -> anyhow::Result<()> {
+    let keyring = encryption::keyring().ok_or(anyhow!("ENCRYPTION_KEY is required"))?;
+
+    let rotation = key_rotation::rotate(conn, keyring, batch_size).await?;
+    tracing::info!(
+        "re-encrypted {} of {} users with key version {}",
+        rotation.rotated,
+        rotation.scanned,
+        keyring.current_version()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,684 @@
+#[path = "config_test.rs"]
+#[cfg(test)]
+mod config_test;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::http::{header, HeaderName, Method};
+use thiserror::Error;
+use url::Url;
+
+use crate::database::{self, SslMode};
+use crate::encryption::Keyring;
+use crate::handlers::cors::AllowedOrigin;
+use crate::logging::LogFormat;
+use crate::telemetry;
+
+pub const DEFAULT_PORT: u16 = 7000;
+pub const DEFAULT_SHUTDOWN_READINESS_DELAY: Duration = Duration::from_secs(5);
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(20);
+pub const DEFAULT_DB_MAX_CONNECTIONS: u32 = 10;
+pub const DEFAULT_DB_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_DB_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_DB_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+pub const DEFAULT_DB_SLOW_STATEMENT_THRESHOLD: Duration = Duration::from_secs(1);
+pub const DEFAULT_CORS_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+pub const DEFAULT_DELETED_USER_RETENTION_DAYS: u64 = 30;
+pub const DEFAULT_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// an exact version, so the script can be checked against DOCS_REDOC_INTEGRITY
+pub const DEFAULT_REDOC_URL: &str =
+    "https://cdn.jsdelivr.net/npm/redoc@2.1.5/bundles/redoc.standalone.js";
+
+// a value that must never end up in logs or error messages
+#[derive(Clone, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Secret {
+        Secret(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid configuration:\n  {}", .0.join("\n  "))]
+pub struct ConfigError(pub Vec<String>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuthorizationEngine {
+    Builtin,
+    Oso,
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub shutdown: ShutdownConfig,
+    pub database: DatabaseConfig,
+    pub auth0: Auth0Config,
+    pub authorization: AuthorizationConfig,
+    pub cors: CorsConfig,
+    pub docs: DocsConfig,
+    pub encryption: EncryptionConfig,
+    pub retention: RetentionConfig,
+    pub telemetry: TelemetryConfig,
+}
+
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    pub port: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShutdownConfig {
+    // how long readiness fails before the listener closes, so load balancers
+    // can stop routing to this instance
+    pub readiness_delay: Duration,
+    // how long in-flight requests may take to finish once the listener closes
+    pub drain_timeout: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct DatabaseConfig {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: Secret,
+    pub name: String,
+    pub ssl_mode: SslMode,
+    // CA bundle the server certificate is verified against, e.g. the RDS
+    // global bundle, instead of the bundled web roots
+    pub ssl_root_cert: Option<String>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout: Duration,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub statement_log_level: log::LevelFilter,
+    // statements taking longer are logged as warnings
+    pub slow_statement_threshold: Duration,
+    // applies pending migrations before the server starts
+    pub migrate_on_startup: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Auth0Config {
+    pub domain: String,
+    pub audience: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct AuthorizationConfig {
+    pub engine: AuthorizationEngine,
+    // empty to use the policies bundled into the binary
+    pub policy_files: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CorsConfig {
+    // no origins are allowed unless configured
+    pub allowed_origins: Vec<AllowedOrigin>,
+    pub allowed_methods: Vec<Method>,
+    pub allowed_headers: Vec<HeaderName>,
+    pub allow_credentials: bool,
+    pub max_age: Duration,
+}
+
+impl Default for CorsConfig {
+    fn default() -> CorsConfig {
+        CorsConfig {
+            allowed_origins: Vec::new(),
+            allowed_methods: vec![
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ],
+            allowed_headers: vec![
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                header::IF_MATCH,
+                header::IF_NONE_MATCH,
+                HeaderName::from_static(telemetry::REQUEST_ID_HEADER),
+                HeaderName::from_static("traceparent"),
+                HeaderName::from_static("tracestate"),
+            ],
+            allow_credentials: false,
+            max_age: DEFAULT_CORS_MAX_AGE,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DocsConfig {
+    // serves the Redoc UI at /docs, the spec is always served
+    pub ui: bool,
+    // where the UI loads Redoc from, e.g. a self-hosted copy
+    pub redoc_url: String,
+    // subresource integrity hash of the script at redoc_url, required with
+    // the UI so a changed script is refused by the browser
+    pub redoc_integrity: Option<String>,
+}
+
+impl Default for DocsConfig {
+    fn default() -> DocsConfig {
+        DocsConfig {
+            ui: false,
+            redoc_url: DEFAULT_REDOC_URL.to_owned(),
+            redoc_integrity: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RetentionConfig {
+    // how long deleted users can be restored before they are purged
+    pub deleted_users: Duration,
+    // how often the server purges
+    pub purge_interval: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct EncryptionConfig {
+    // prints only the key versions
+    pub keyring: Option<Keyring>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TelemetryConfig {
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    pub log_format: LogFormat,
+}
+
+impl Config {
+    // reads the optional TOML file and the process environment, environment
+    // variables take precedence over the file
+    pub fn load(file: Option<&Path>) -> Result<Config, ConfigError> {
+        let contents = read_file(file)?;
+        Config::from_sources(contents.as_deref(), std::env::vars().collect())
+    }
+
+    pub fn from_sources(
+        file: Option<&str>,
+        env: HashMap<String, String>,
+    ) -> Result<Config, ConfigError> {
+        let mut reader = Reader::new(file, env)?;
+
+        let config = Config {
+            server: reader.server(),
+            shutdown: reader.shutdown(),
+            database: reader.database(),
+            auth0: reader.auth0(),
+            authorization: reader.authorization(),
+            cors: reader.cors(),
+            docs: reader.docs(),
+            encryption: reader.encryption(),
+            retention: reader.retention(),
+            telemetry: reader.telemetry(),
+        };
+
+        reader.finish(config)
+    }
+}
+
+// the settings used by the database commands, which run without the server's
+// Auth0, authorization and CORS settings
+#[derive(Clone, Debug)]
+pub struct CommandConfig {
+    pub database: DatabaseConfig,
+    pub encryption: EncryptionConfig,
+    pub telemetry: TelemetryConfig,
+}
+
+impl CommandConfig {
+    pub fn load(file: Option<&Path>) -> Result<CommandConfig, ConfigError> {
+        let contents = read_file(file)?;
+        CommandConfig::from_sources(contents.as_deref(), std::env::vars().collect())
+    }
+
+    pub fn from_sources(
+        file: Option<&str>,
+        env: HashMap<String, String>,
+    ) -> Result<CommandConfig, ConfigError> {
+        let mut reader = Reader::new(file, env)?;
+
+        let config = CommandConfig {
+            database: reader.database(),
+            encryption: reader.encryption(),
+            telemetry: reader.telemetry(),
+        };
+
+        reader.finish(config)
+    }
+}
+
+fn read_file(file: Option<&Path>) -> Result<Option<String>, ConfigError> {
+    file.map(|file| {
+        std::fs::read_to_string(file)
+            .map_err(|err| ConfigError(vec![format!("reading {}: {}", file.display(), err)]))
+    })
+    .transpose()
+}
+
+// looks settings up by environment variable first, then by their dotted path
+// in the config file, collecting every problem instead of stopping at the first
+struct Reader {
+    file: toml::Table,
+    env: HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl Reader {
+    fn new(file: Option<&str>, env: HashMap<String, String>) -> Result<Reader, ConfigError> {
+        let file = match file {
+            Some(contents) => toml::from_str::<toml::Table>(contents)
+                .map_err(|err| ConfigError(vec![format!("parsing config file: {}", err)]))?,
+            None => toml::Table::new(),
+        };
+
+        Ok(Reader {
+            file,
+            env,
+            errors: Vec::new(),
+        })
+    }
+
+    fn finish<T>(self, config: T) -> Result<T, ConfigError> {
+        match self.errors.is_empty() {
+            true => Ok(config),
+            false => Err(ConfigError(self.errors)),
+        }
+    }
+
+    fn server(&mut self) -> ServerConfig {
+        ServerConfig {
+            port: self.parse("PORT", "server.port", DEFAULT_PORT),
+        }
+    }
+
+    fn shutdown(&mut self) -> ShutdownConfig {
+        ShutdownConfig {
+            readiness_delay: Duration::from_secs(self.parse(
+                "SHUTDOWN_READINESS_DELAY",
+                "shutdown.readiness_delay",
+                DEFAULT_SHUTDOWN_READINESS_DELAY.as_secs(),
+            )),
+            drain_timeout: Duration::from_secs(self.parse(
+                "SHUTDOWN_TIMEOUT",
+                "shutdown.drain_timeout",
+                DEFAULT_SHUTDOWN_TIMEOUT.as_secs(),
+            )),
+        }
+    }
+
+    fn database(&mut self) -> DatabaseConfig {
+        let db = DatabaseConfig {
+            host: self.required("DB_HOST", "database.host"),
+            port: self.parse_required("DB_PORT", "database.port"),
+            user: self.required("DB_USER", "database.user"),
+            password: Secret::new(self.required("DB_PASSWORD", "database.password")),
+            name: self.required("DB_NAME", "database.name"),
+            ssl_mode: self.ssl_mode(),
+            ssl_root_cert: self.optional("DB_SSL_ROOT_CERT", "database.ssl_root_cert"),
+            max_connections: self.parse(
+                "DB_MAX_CONNECTIONS",
+                "database.max_connections",
+                DEFAULT_DB_MAX_CONNECTIONS,
+            ),
+            min_connections: self.parse("DB_MIN_CONNECTIONS", "database.min_connections", 0),
+            connect_timeout: Duration::from_secs(self.parse(
+                "DB_CONNECT_TIMEOUT",
+                "database.connect_timeout",
+                DEFAULT_DB_CONNECT_TIMEOUT.as_secs(),
+            )),
+            acquire_timeout: Duration::from_secs(self.parse(
+                "DB_ACQUIRE_TIMEOUT",
+                "database.acquire_timeout",
+                DEFAULT_DB_ACQUIRE_TIMEOUT.as_secs(),
+            )),
+            idle_timeout: Duration::from_secs(self.parse(
+                "DB_IDLE_TIMEOUT",
+                "database.idle_timeout",
+                DEFAULT_DB_IDLE_TIMEOUT.as_secs(),
+            )),
+            statement_log_level: self.parse(
+                "DB_STATEMENT_LOG_LEVEL",
+                "database.statement_log_level",
+                log::LevelFilter::Debug,
+            ),
+            slow_statement_threshold: Duration::from_millis(self.parse(
+                "DB_SLOW_STATEMENT_THRESHOLD",
+                "database.slow_statement_threshold",
+                DEFAULT_DB_SLOW_STATEMENT_THRESHOLD.as_millis() as u64,
+            )),
+            migrate_on_startup: self.parse(
+                "DB_MIGRATE_ON_STARTUP",
+                "database.migrate_on_startup",
+                false,
+            ),
+        };
+
+        if db.max_connections == 0 || db.min_connections > db.max_connections {
+            self.errors.push(format!(
+                "DB_MAX_CONNECTIONS (database.max_connections): must be at least 1 and DB_MIN_CONNECTIONS ({})",
+                db.min_connections
+            ));
+        }
+
+        if let Some(file) = db
+            .ssl_root_cert
+            .as_ref()
+            .filter(|file| !Path::new(file).is_file())
+        {
+            self.errors.push(format!(
+                "DB_SSL_ROOT_CERT (database.ssl_root_cert): {} does not exist",
+                file
+            ));
+        }
+
+        if !db.host.is_empty() {
+            if let Err(err) = database::url(&db) {
+                self.errors
+                    .push(format!("DB_HOST (database.host): {}", err));
+            }
+        }
+
+        db
+    }
+
+    fn auth0(&mut self) -> Auth0Config {
+        Auth0Config {
+            domain: self.required("AUTH0_DOMAIN", "auth0.domain"),
+            audience: self.required("AUTH0_AUDIENCE", "auth0.audience"),
+        }
+    }
+
+    fn authorization(&mut self) -> AuthorizationConfig {
+        let authorization = AuthorizationConfig {
+            engine: self.authorization_engine(),
+            policy_files: self.list(
+                "AUTHORIZATION_POLICY_FILES",
+                "authorization.policy_files",
+                Vec::new(),
+            ),
+        };
+
+        for file in &authorization.policy_files {
+            if !Path::new(file).is_file() {
+                self.errors.push(format!(
+                    "AUTHORIZATION_POLICY_FILES (authorization.policy_files): {} does not exist",
+                    file
+                ));
+            }
+        }
+
+        authorization
+    }
+
+    fn cors(&mut self) -> CorsConfig {
+        let defaults = CorsConfig::default();
+
+        let cors = CorsConfig {
+            allowed_origins: self.list(
+                "ALLOWED_ORIGINS",
+                "cors.allowed_origins",
+                defaults.allowed_origins,
+            ),
+            allowed_methods: self.list(
+                "CORS_ALLOWED_METHODS",
+                "cors.allowed_methods",
+                defaults.allowed_methods,
+            ),
+            allowed_headers: self.list(
+                "CORS_ALLOWED_HEADERS",
+                "cors.allowed_headers",
+                defaults.allowed_headers,
+            ),
+            allow_credentials: self.parse(
+                "CORS_ALLOW_CREDENTIALS",
+                "cors.allow_credentials",
+                defaults.allow_credentials,
+            ),
+            max_age: Duration::from_secs(self.parse(
+                "CORS_MAX_AGE",
+                "cors.max_age",
+                defaults.max_age.as_secs(),
+            )),
+        };
+
+        if cors.allow_credentials && cors.allowed_origins.contains(&AllowedOrigin::Any) {
+            self.errors.push(
+                "ALLOWED_ORIGINS (cors.allowed_origins): \"*\" can't be combined with CORS_ALLOW_CREDENTIALS"
+                    .to_owned(),
+            );
+        }
+
+        cors
+    }
+
+    fn docs(&mut self) -> DocsConfig {
+        let defaults = DocsConfig::default();
+
+        let docs = DocsConfig {
+            ui: self.parse("DOCS_UI", "docs.ui", defaults.ui),
+            redoc_url: self
+                .optional("DOCS_REDOC_URL", "docs.redoc_url")
+                .unwrap_or(defaults.redoc_url),
+            redoc_integrity: self.optional("DOCS_REDOC_INTEGRITY", "docs.redoc_integrity"),
+        };
+
+        if let Err(err) = Url::parse(&docs.redoc_url) {
+            self.errors.push(format!(
+                "DOCS_REDOC_URL (docs.redoc_url): invalid value {:?}: {err}",
+                docs.redoc_url
+            ));
+        }
+
+        match &docs.redoc_integrity {
+            None if docs.ui => self.errors.push(
+                "DOCS_REDOC_INTEGRITY (docs.redoc_integrity) is required when DOCS_UI is enabled"
+                    .to_owned(),
+            ),
+            Some(integrity)
+                if !["sha256-", "sha384-", "sha512-"]
+                    .iter()
+                    .any(|prefix| integrity.starts_with(prefix)) =>
+            {
+                self.errors.push(format!(
+                    "DOCS_REDOC_INTEGRITY (docs.redoc_integrity): invalid value {integrity:?}, expected sha256-, sha384- or sha512- followed by a base64 digest"
+                ))
+            }
+            _ => {}
+        }
+
+        docs
+    }
+
+    fn encryption(&mut self) -> EncryptionConfig {
+        // the key isn't echoed back in the error, unlike other settings
+        let keyring = self
+            .optional("ENCRYPTION_KEY", "encryption.key")
+            .and_then(|key| match key.parse::<Keyring>() {
+                Ok(keyring) => Some(keyring),
+                Err(err) => {
+                    self.errors
+                        .push(format!("ENCRYPTION_KEY (encryption.key): {err}"));
+                    None
+                }
+            });
+
+        EncryptionConfig { keyring }
+    }
+
+    fn retention(&mut self) -> RetentionConfig {
+        let retention = RetentionConfig {
+            deleted_users: Duration::from_secs(
+                self.parse(
+                    "DELETED_USER_RETENTION_DAYS",
+                    "retention.deleted_user_days",
+                    DEFAULT_DELETED_USER_RETENTION_DAYS,
+                )
+                .saturating_mul(24 * 60 * 60),
+            ),
+            purge_interval: Duration::from_secs(self.parse(
+                "PURGE_INTERVAL",
+                "retention.purge_interval",
+                DEFAULT_PURGE_INTERVAL.as_secs(),
+            )),
+        };
+
+        if retention.purge_interval.is_zero() {
+            self.errors
+                .push("PURGE_INTERVAL (retention.purge_interval): must be at least 1".to_owned());
+        }
+
+        retention
+    }
+
+    fn telemetry(&mut self) -> TelemetryConfig {
+        TelemetryConfig {
+            otlp_endpoint: self.optional("OTEL_EXPORTER_OTLP_ENDPOINT", "telemetry.otlp_endpoint"),
+            service_name: self
+                .optional("OTEL_SERVICE_NAME", "telemetry.service_name")
+                .unwrap_or_else(|| telemetry::DEFAULT_SERVICE_NAME.to_owned()),
+            log_format: self.log_format(),
+        }
+    }
+
+    fn optional(&mut self, env: &str, path: &str) -> Option<String> {
+        if let Some(value) = self.env.get(env).filter(|value| !value.is_empty()) {
+            return Some(value.to_owned());
+        }
+
+        let mut keys = path.split('.');
+        let first = self.file.get(keys.next()?);
+        let value = keys.try_fold(first?, |value, key| value.get(key))?;
+
+        match value {
+            toml::Value::String(value) if value.is_empty() => None,
+            toml::Value::String(value) => Some(value.to_owned()),
+            toml::Value::Array(values) => Some(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        toml::Value::String(value) => value.to_owned(),
+                        value => value.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+            value => Some(value.to_string()),
+        }
+    }
+
+    fn required(&mut self, env: &str, path: &str) -> String {
+        self.optional(env, path).unwrap_or_else(|| {
+            self.errors.push(format!("{env} ({path}) is required"));
+            String::new()
+        })
+    }
+
+    fn parse<T: FromStr>(&mut self, env: &str, path: &str, default: T) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        match self.optional(env, path) {
+            Some(value) => value.parse().unwrap_or_else(|err| {
+                self.errors
+                    .push(format!("{env} ({path}): invalid value {value:?}: {err}"));
+                default
+            }),
+            None => default,
+        }
+    }
+
+    fn parse_required<T: FromStr + Default>(&mut self, env: &str, path: &str) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        if self.optional(env, path).is_none() {
+            self.errors.push(format!("{env} ({path}) is required"));
+        }
+        self.parse(env, path, T::default())
+    }
+
+    // a comma separated list in the environment or an array in the file
+    fn list<T: FromStr>(&mut self, env: &str, path: &str, default: Vec<T>) -> Vec<T>
+    where
+        T::Err: fmt::Display,
+    {
+        let Some(value) = self.optional(env, path) else {
+            return default;
+        };
+
+        value
+            .split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .filter_map(|item| {
+                item.parse()
+                    .map_err(|err| {
+                        self.errors
+                            .push(format!("{env} ({path}): invalid value {item:?}: {err}"))
+                    })
+                    .ok()
+            })
+            .collect()
+    }
+
+    // DB_SSL_MODE takes precedence, otherwise DB_SSL switches between
+    // requiring and disabling SSL
+    fn ssl_mode(&mut self) -> SslMode {
+        let default = match self.parse("DB_SSL", "database.ssl", false) {
+            true => SslMode::Require,
+            false => SslMode::Disable,
+        };
+        self.parse("DB_SSL_MODE", "database.ssl_mode", default)
+    }
+
+    fn authorization_engine(&mut self) -> AuthorizationEngine {
+        match self
+            .optional("AUTHORIZATION_ENGINE", "authorization.engine")
+            .as_deref()
+        {
+            None | Some("builtin") => AuthorizationEngine::Builtin,
+            Some("oso") => AuthorizationEngine::Oso,
+            Some(engine) => {
+                self.errors.push(format!(
+                    "AUTHORIZATION_ENGINE (authorization.engine): unknown engine {engine:?}, expected \"builtin\" or \"oso\""
+                ));
+                AuthorizationEngine::Builtin
+            }
+        }
+    }
+
+    fn log_format(&mut self) -> LogFormat {
+        let format = self.optional("LOG_FORMAT", "telemetry.log_format");
+        match format.as_deref().map(LogFormat::parse) {
+            None => LogFormat::Text,
+            Some(Some(format)) => format,
+            Some(None) => {
+                self.errors.push(format!(
+                    "LOG_FORMAT (telemetry.log_format): unknown format {:?}, expected \"text\" or \"json\"",
+                    format.unwrap_or_default()
+                ));
+                LogFormat::Text
+            }
+        }
+    }
+}
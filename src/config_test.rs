@@ -0,0 +1,256 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use axum::http::Method;
+
+    use crate::config::{
+        AuthorizationEngine, CommandConfig, Config, DEFAULT_DB_MAX_CONNECTIONS, DEFAULT_PORT,
+        DEFAULT_PURGE_INTERVAL,
+    };
+    use crate::database::SslMode;
+    use crate::handlers::cors::AllowedOrigin;
+    use crate::logging::LogFormat;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn required_env() -> HashMap<String, String> {
+        env(&[
+            ("DB_HOST", "localhost"),
+            ("DB_PORT", "5432"),
+            ("DB_USER", "postgres"),
+            ("DB_PASSWORD", "hunter2"),
+            ("DB_NAME", "users"),
+            ("AUTH0_DOMAIN", "example.auth0.com"),
+            ("AUTH0_AUDIENCE", "https://api.example.com"),
+        ])
+    }
+
+    #[test]
+    fn test_from_env() {
+        let mut vars = required_env();
+        vars.extend(env(&[
+            ("DB_SSL", "true"),
+            (
+                "ALLOWED_ORIGINS",
+                "https://app.example.com, https://admin.example.com",
+            ),
+            ("AUTHORIZATION_ENGINE", "oso"),
+            ("LOG_FORMAT", "json"),
+        ]));
+
+        let config = Config::from_sources(None, vars).unwrap();
+
+        assert_eq!(config.server.port, DEFAULT_PORT);
+        assert_eq!(config.database.port, 5432);
+        assert_eq!(config.database.ssl_mode, SslMode::Require);
+        assert_eq!(config.database.max_connections, DEFAULT_DB_MAX_CONNECTIONS);
+        assert_eq!(
+            config.cors.allowed_origins,
+            vec![
+                AllowedOrigin::Exact("https://app.example.com".to_owned()),
+                AllowedOrigin::Exact("https://admin.example.com".to_owned()),
+            ]
+        );
+        assert!(config.cors.allowed_methods.contains(&Method::PUT));
+        assert!(!config.cors.allow_credentials);
+        assert_eq!(config.authorization.engine, AuthorizationEngine::Oso);
+        assert!(config.authorization.policy_files.is_empty());
+        assert_eq!(config.telemetry.log_format, LogFormat::Json);
+        assert!(config.telemetry.otlp_endpoint.is_none());
+        assert!(config.encryption.keyring.is_none());
+        assert_eq!(
+            config.retention.deleted_users,
+            Duration::from_secs(30 * 24 * 60 * 60)
+        );
+        assert_eq!(config.retention.purge_interval, DEFAULT_PURGE_INTERVAL);
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let file = r#"
+            [server]
+            port = 8080
+
+            [database]
+            host = "db.internal"
+            port = 5433
+            user = "app"
+            password = "from-file"
+            name = "users"
+            ssl = true
+            ssl_mode = "verify-full"
+            idle_timeout = 30
+
+            [auth0]
+            domain = "example.auth0.com"
+            audience = "https://api.example.com"
+
+            [cors]
+            allowed_origins = ["https://*.example.com"]
+            allowed_methods = ["GET"]
+            max_age = 60
+
+            [telemetry]
+            service_name = "users-api"
+        "#;
+
+        let config = Config::from_sources(Some(file), env(&[("DB_PASSWORD", "from-env")])).unwrap();
+
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.database.host, "db.internal");
+        assert_eq!(config.database.port, 5433);
+        assert_eq!(config.database.password.expose(), "from-env");
+        assert_eq!(config.database.ssl_mode, SslMode::VerifyFull);
+        assert_eq!(config.database.idle_timeout, Duration::from_secs(30));
+        assert_eq!(
+            config.cors.allowed_origins,
+            vec![AllowedOrigin::Subdomains {
+                scheme: "https://".to_owned(),
+                suffix: ".example.com".to_owned(),
+            }]
+        );
+        assert_eq!(config.cors.allowed_methods, vec![Method::GET]);
+        assert_eq!(config.cors.max_age, Duration::from_secs(60));
+        assert_eq!(config.telemetry.service_name, "users-api");
+    }
+
+    #[test]
+    fn test_reports_all_errors() {
+        let vars = env(&[
+            ("DB_PORT", "postgres"),
+            ("PORT", "70000"),
+            ("DB_SSL", "maybe"),
+            ("DB_SSL_MODE", "sometimes"),
+            ("DB_SSL_ROOT_CERT", "missing.pem"),
+            ("DB_MIN_CONNECTIONS", "20"),
+            ("AUTHORIZATION_ENGINE", "opa"),
+            ("AUTHORIZATION_POLICY_FILES", "missing.polar"),
+            ("LOG_FORMAT", "xml"),
+            ("PURGE_INTERVAL", "0"),
+            ("ALLOWED_ORIGINS", "*,app.example.com"),
+            ("CORS_ALLOWED_HEADERS", "authorization,bad header"),
+            ("CORS_ALLOW_CREDENTIALS", "true"),
+        ]);
+
+        let errors = Config::from_sources(None, vars).unwrap_err().0;
+
+        let expected = [
+            "PORT (server.port): invalid value \"70000\"",
+            "DB_HOST (database.host) is required",
+            "DB_PORT (database.port): invalid value \"postgres\"",
+            "DB_USER (database.user) is required",
+            "DB_PASSWORD (database.password) is required",
+            "DB_NAME (database.name) is required",
+            "DB_SSL (database.ssl): invalid value \"maybe\"",
+            "DB_SSL_MODE (database.ssl_mode): invalid value \"sometimes\"",
+            "DB_MAX_CONNECTIONS (database.max_connections): must be at least 1",
+            "DB_SSL_ROOT_CERT (database.ssl_root_cert): missing.pem does not exist",
+            "AUTH0_DOMAIN (auth0.domain) is required",
+            "AUTH0_AUDIENCE (auth0.audience) is required",
+            "AUTHORIZATION_ENGINE (authorization.engine): unknown engine \"opa\"",
+            "AUTHORIZATION_POLICY_FILES (authorization.policy_files): missing.polar does not exist",
+            "ALLOWED_ORIGINS (cors.allowed_origins): invalid value \"app.example.com\"",
+            "CORS_ALLOWED_HEADERS (cors.allowed_headers): invalid value \"bad header\"",
+            "ALLOWED_ORIGINS (cors.allowed_origins): \"*\" can't be combined with CORS_ALLOW_CREDENTIALS",
+            "PURGE_INTERVAL (retention.purge_interval): must be at least 1",
+            "LOG_FORMAT (telemetry.log_format): unknown format \"xml\"",
+        ];
+
+        assert_eq!(errors.len(), expected.len(), "{errors:#?}");
+        for (error, expected) in errors.iter().zip(expected) {
+            assert!(error.starts_with(expected), "{error}");
+        }
+    }
+
+    #[test]
+    fn test_command_config_needs_only_database() {
+        let vars = env(&[
+            ("DB_HOST", "localhost"),
+            ("DB_PORT", "5432"),
+            ("DB_USER", "postgres"),
+            ("DB_PASSWORD", "hunter2"),
+            ("DB_NAME", "users"),
+        ]);
+
+        let config = CommandConfig::from_sources(None, vars.clone()).unwrap();
+        assert_eq!(config.database.host, "localhost");
+
+        assert!(Config::from_sources(None, vars).is_err());
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let errors = Config::from_sources(Some("[database"), required_env())
+            .unwrap_err()
+            .0;
+
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("parsing config file"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let mut vars = required_env();
+        vars.insert(
+            "ENCRYPTION_KEY".to_owned(),
+            "2:AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=".to_owned(),
+        );
+
+        let config = Config::from_sources(None, vars).unwrap();
+        let debug = format!("{config:?}");
+
+        assert!(!debug.contains("hunter2"), "{debug}");
+        assert!(!debug.contains("AAECAwQF"), "{debug}");
+        assert!(debug.contains("[redacted]"));
+        assert!(debug.contains("versions: [2]"), "{debug}");
+    }
+
+    #[test]
+    fn test_invalid_encryption_key() {
+        let mut vars = required_env();
+        vars.insert("ENCRYPTION_KEY".to_owned(), "not-a-key".to_owned());
+
+        let errors = Config::from_sources(None, vars).unwrap_err().0;
+
+        // the key isn't echoed back
+        assert_eq!(
+            errors,
+            vec!["ENCRYPTION_KEY (encryption.key): key version 1 isn't valid base64"]
+        );
+    }
+
+    #[test]
+    fn test_docs_ui_requires_integrity() {
+        let config = Config::from_sources(None, required_env()).unwrap();
+        assert!(!config.docs.ui);
+
+        let mut vars = required_env();
+        vars.insert("DOCS_UI".to_owned(), "true".to_owned());
+
+        let errors = Config::from_sources(None, vars.clone()).unwrap_err().0;
+        assert_eq!(
+            errors,
+            vec!["DOCS_REDOC_INTEGRITY (docs.redoc_integrity) is required when DOCS_UI is enabled"]
+        );
+
+        vars.insert("DOCS_REDOC_INTEGRITY".to_owned(), "md5-abc".to_owned());
+        let errors = Config::from_sources(None, vars.clone()).unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("DOCS_REDOC_INTEGRITY (docs.redoc_integrity): invalid value"));
+
+        vars.insert("DOCS_REDOC_INTEGRITY".to_owned(), "sha384-abc".to_owned());
+        let config = Config::from_sources(None, vars).unwrap();
+        assert!(config.docs.ui);
+        assert_eq!(config.docs.redoc_integrity.as_deref(), Some("sha384-abc"));
+    }
+}
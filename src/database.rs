@@ -0,0 +1,91 @@
+#[path = "database_test.rs"]
+#[cfg(test)]
+mod database_test;
+
+use std::str::FromStr;
+
+use sea_orm::ConnectOptions;
+use url::Url;
+
+use crate::config::DatabaseConfig;
+
+// the libpq sslmode values understood by sqlx
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SslMode::Disable => "disable",
+            SslMode::Allow => "allow",
+            SslMode::Prefer => "prefer",
+            SslMode::Require => "require",
+            SslMode::VerifyCa => "verify-ca",
+            SslMode::VerifyFull => "verify-full",
+        }
+    }
+}
+
+impl FromStr for SslMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<SslMode, String> {
+        match mode.to_lowercase().as_str() {
+            "disable" => Ok(SslMode::Disable),
+            "allow" => Ok(SslMode::Allow),
+            "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            _ => Err(
+                "expected one of disable, allow, prefer, require, verify-ca, verify-full"
+                    .to_owned(),
+            ),
+        }
+    }
+}
+
+// the connection url, with the credentials percent-encoded so they may
+// contain any character
+pub fn url(config: &DatabaseConfig) -> Result<Url, url::ParseError> {
+    let mut url = Url::parse(&format!("postgres://{}:{}", config.host, config.port))?;
+
+    url.set_path(&config.name);
+    url.set_username(&config.user)
+        .and_then(|_| url.set_password(Some(config.password.expose())))
+        .map_err(|_| url::ParseError::EmptyHost)?;
+
+    url.query_pairs_mut()
+        .append_pair("sslmode", config.ssl_mode.as_str());
+    if let Some(ssl_root_cert) = &config.ssl_root_cert {
+        url.query_pairs_mut()
+            .append_pair("sslrootcert", ssl_root_cert);
+    }
+
+    Ok(url)
+}
+
+pub fn connect_options(config: &DatabaseConfig) -> Result<ConnectOptions, url::ParseError> {
+    let mut options = ConnectOptions::new(url(config)?);
+
+    options
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .connect_timeout(config.connect_timeout)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(config.idle_timeout)
+        .sqlx_logging_level(config.statement_log_level)
+        .sqlx_slow_statements_logging_settings(
+            log::LevelFilter::Warn,
+            config.slow_statement_threshold,
+        );
+
+    Ok(options)
+}
@@ -0,0 +1,334 @@
+#[path = "encryption_test.rs"]
+#[cfg(test)]
+mod encryption_test;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use sea_orm::sea_query::{ArrayType, ColumnType, Nullable, ValueType, ValueTypeErr};
+use sea_orm::{ColIdx, DbErr, QueryResult, TryGetError, TryGetable, Value};
+use sha2::Sha256;
+use thiserror::Error;
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+
+// ciphertexts are stored as "enc:v<version>:<base64 nonce and ciphertext>",
+// anything else is a plaintext value written before encryption was enabled
+const PREFIX: &str = "enc:v";
+
+// set once at startup, before the database is used; columns of type
+// `Encrypted` are stored in plaintext while it is unset
+static KEYRING: OnceLock<Keyring> = OnceLock::new();
+
+pub fn init(keyring: Keyring) {
+    if KEYRING.set(keyring).is_err() {
+        tracing::warn!("the encryption keyring was already initialized");
+    }
+}
+
+pub fn keyring() -> Option<&'static Keyring> {
+    KEYRING.get()
+}
+
+pub fn is_enabled() -> bool {
+    KEYRING.get().is_some()
+}
+
+// the blind index of `value` under the current key; while encryption is
+// disabled it is the value itself, as stored in the column it indexes, so the
+// index is the uniqueness key either way
+pub fn blind_index(value: &str) -> String {
+    match keyring() {
+        Some(keyring) => keyring.blind_index(value),
+        None => value.to_owned(),
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EncryptionError {
+    #[error("value was encrypted with unknown key version {0}")]
+    UnknownVersion(u32),
+    #[error("value is encrypted but no ENCRYPTION_KEY is configured")]
+    NoKey,
+    #[error("malformed ciphertext")]
+    Malformed,
+    #[error("ciphertext failed authentication")]
+    Authentication,
+}
+
+#[derive(Clone)]
+struct Key {
+    version: u32,
+    cipher: XChaCha20Poly1305,
+    index_key: [u8; KEY_LENGTH],
+}
+
+impl Key {
+    // separate keys for encryption and the blind index are derived from the
+    // configured key, so neither use weakens the other
+    fn new(version: u32, key: &[u8]) -> Key {
+        Key {
+            version,
+            cipher: XChaCha20Poly1305::new(&derive(key, b"encryption").into()),
+            index_key: derive(key, b"blind-index"),
+        }
+    }
+}
+
+fn derive(key: &[u8], purpose: &[u8]) -> [u8; KEY_LENGTH] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(purpose);
+    mac.finalize().into_bytes().into()
+}
+
+// the current key, which encrypts, followed by older keys that only decrypt
+// until `rotate-keys` has re-encrypted everything
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Vec<Key>,
+}
+
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field(
+                "versions",
+                &self
+                    .keys
+                    .iter()
+                    .map(|key| key.version)
+                    .collect::<Vec<u32>>(),
+            )
+            .finish()
+    }
+}
+
+// a comma separated list of "<version>:<base64 key>", newest first; a single
+// key without a version is version 1
+impl FromStr for Keyring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys = Vec::new();
+
+        for entry in s.split(',').map(str::trim) {
+            let (version, key) = match entry.split_once(':') {
+                Some((version, key)) => (
+                    version
+                        .parse::<u32>()
+                        .map_err(|_| format!("invalid key version {version:?}"))?,
+                    key,
+                ),
+                None => (1, entry),
+            };
+
+            let key = STANDARD
+                .decode(key)
+                .map_err(|_| format!("key version {version} isn't valid base64"))?;
+            if key.len() != KEY_LENGTH {
+                return Err(format!(
+                    "key version {version} must be {KEY_LENGTH} bytes, got {}",
+                    key.len()
+                ));
+            }
+
+            if keys
+                .iter()
+                .any(|existing: &Key| existing.version == version)
+            {
+                return Err(format!("key version {version} is listed twice"));
+            }
+
+            keys.push(Key::new(version, &key));
+        }
+
+        Ok(Keyring { keys })
+    }
+}
+
+impl Keyring {
+    fn current(&self) -> &Key {
+        &self.keys[0]
+    }
+
+    pub fn current_version(&self) -> u32 {
+        self.current().version
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let key = self.current();
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let header = format!("{PREFIX}{}:", key.version);
+
+        // the header is authenticated so the version can't be swapped
+        let ciphertext = key
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: header.as_bytes(),
+                },
+            )
+            .expect("encrypting into a vec doesn't fail");
+
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+
+        format!("{header}{}", URL_SAFE_NO_PAD.encode(payload))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String, EncryptionError> {
+        let Some((version, payload)) = parse(value)? else {
+            return Ok(value.to_owned());
+        };
+
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.version == version)
+            .ok_or(EncryptionError::UnknownVersion(version))?;
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| EncryptionError::Malformed)?;
+        if payload.len() < NONCE_LENGTH {
+            return Err(EncryptionError::Malformed);
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+
+        let header = format!("{PREFIX}{version}:");
+        let plaintext = key
+            .cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::Authentication)?;
+
+        String::from_utf8(plaintext).map_err(|_| EncryptionError::Malformed)
+    }
+
+    // a keyed hash that allows exact-match lookups without decrypting
+    pub fn blind_index(&self, value: &str) -> String {
+        index(self.current(), value)
+    }
+
+    // the blind index under every key, to find rows that haven't been
+    // rotated to the current key yet
+    pub fn blind_indexes(&self, value: &str) -> Vec<String> {
+        self.keys.iter().map(|key| index(key, value)).collect()
+    }
+}
+
+fn index(key: &Key, value: &str) -> String {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(&key.index_key).expect("hmac accepts any key length");
+    mac.update(value.as_bytes());
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+// the key version and payload of a ciphertext, or None for plaintext
+fn parse(value: &str) -> Result<Option<(u32, &str)>, EncryptionError> {
+    let Some(rest) = value.strip_prefix(PREFIX) else {
+        return Ok(None);
+    };
+
+    let (version, payload) = rest.split_once(':').ok_or(EncryptionError::Malformed)?;
+    let version = version
+        .parse::<u32>()
+        .map_err(|_| EncryptionError::Malformed)?;
+
+    Ok(Some((version, payload)))
+}
+
+// the key version a stored value was encrypted with, None for plaintext
+pub fn version(value: &str) -> Option<u32> {
+    parse(value).ok().flatten().map(|(version, _)| version)
+}
+
+fn decrypt(value: String) -> Result<String, EncryptionError> {
+    match keyring() {
+        Some(keyring) => keyring.decrypt(&value),
+        None if version(&value).is_some() => Err(EncryptionError::NoKey),
+        None => Ok(value),
+    }
+}
+
+// a text column that is encrypted when written and decrypted when read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Encrypted(pub String);
+
+impl From<String> for Encrypted {
+    fn from(value: String) -> Self {
+        Encrypted(value)
+    }
+}
+
+impl From<&str> for Encrypted {
+    fn from(value: &str) -> Self {
+        Encrypted(value.to_owned())
+    }
+}
+
+impl From<Encrypted> for String {
+    fn from(value: Encrypted) -> Self {
+        value.0
+    }
+}
+
+impl From<Encrypted> for Value {
+    fn from(value: Encrypted) -> Self {
+        let stored = match keyring() {
+            Some(keyring) => keyring.encrypt(&value.0),
+            None => value.0,
+        };
+        Value::String(Some(Box::new(stored)))
+    }
+}
+
+impl TryGetable for Encrypted {
+    fn try_get_by<I: ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        let value = String::try_get_by(res, index)?;
+        decrypt(value)
+            .map(Encrypted)
+            .map_err(|err| TryGetError::DbErr(DbErr::Type(err.to_string())))
+    }
+}
+
+impl ValueType for Encrypted {
+    fn try_from(v: Value) -> Result<Self, ValueTypeErr> {
+        match v {
+            Value::String(Some(value)) => decrypt(*value).map(Encrypted).map_err(|_| ValueTypeErr),
+            _ => Err(ValueTypeErr),
+        }
+    }
+
+    fn type_name() -> String {
+        "Encrypted".to_owned()
+    }
+
+    fn array_type() -> ArrayType {
+        ArrayType::String
+    }
+
+    fn column_type() -> ColumnType {
+        ColumnType::Text
+    }
+}
+
+impl Nullable for Encrypted {
+    fn null() -> Value {
+        Value::String(None)
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use crate::encryption::{version, EncryptionError, Keyring};
+
+    const KEY_1: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+    const KEY_2: &str = "ICEiIyQlJicoKSorLC0uLzAxMjM0NTY3ODk6Ozw9Pj8=";
+
+    fn keyring(keys: &str) -> Keyring {
+        keys.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(keyring(KEY_1).current_version(), 1);
+        assert_eq!(
+            keyring(&format!("2:{KEY_2}, 1:{KEY_1}")).current_version(),
+            2
+        );
+        assert_eq!(
+            format!("{:?}", keyring(&format!("2:{KEY_2},1:{KEY_1}"))),
+            "Keyring { versions: [2, 1] }"
+        );
+
+        assert!("".parse::<Keyring>().is_err());
+        assert!("c2hvcnQ=".parse::<Keyring>().is_err());
+        assert!(format!("x:{KEY_1}").parse::<Keyring>().is_err());
+        assert!(format!("1:{KEY_1},1:{KEY_2}").parse::<Keyring>().is_err());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let keyring = keyring(&format!("3:{KEY_1}"));
+
+        let first = keyring.encrypt("Ada");
+        let second = keyring.encrypt("Ada");
+
+        // random nonces, so equal values don't show
+        assert_ne!(first, second);
+        assert!(first.starts_with("enc:v3:"), "{first}");
+        assert!(!first.contains("Ada"));
+        assert_eq!(version(&first), Some(3));
+
+        assert_eq!(keyring.decrypt(&first).unwrap(), "Ada");
+        assert_eq!(keyring.decrypt(&second).unwrap(), "Ada");
+    }
+
+    #[test]
+    fn test_decrypt_plaintext() {
+        let keyring = keyring(KEY_1);
+
+        assert_eq!(keyring.decrypt("Ada").unwrap(), "Ada");
+        assert_eq!(version("Ada"), None);
+    }
+
+    #[test]
+    fn test_decrypt_with_older_key() {
+        let old = keyring(&format!("1:{KEY_1}"));
+        let rotated = keyring(&format!("2:{KEY_2},1:{KEY_1}"));
+        let new = keyring(&format!("2:{KEY_2}"));
+
+        let ciphertext = old.encrypt("Ada");
+
+        assert_eq!(rotated.decrypt(&ciphertext).unwrap(), "Ada");
+        assert_eq!(
+            new.decrypt(&ciphertext),
+            Err(EncryptionError::UnknownVersion(1))
+        );
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampering() {
+        let keyring = keyring(&format!("2:{KEY_2},1:{KEY_1}"));
+        let ciphertext = keyring.encrypt("Ada");
+
+        // changing the version changes the authenticated header
+        let relabelled = ciphertext.replacen("enc:v2:", "enc:v1:", 1);
+        assert_eq!(
+            keyring.decrypt(&relabelled),
+            Err(EncryptionError::Authentication)
+        );
+
+        let mut flipped = ciphertext.clone().into_bytes();
+        let middle = flipped.len() / 2;
+        flipped[middle] = if flipped[middle] == b'A' { b'B' } else { b'A' };
+        assert_eq!(
+            keyring.decrypt(&String::from_utf8(flipped).unwrap()),
+            Err(EncryptionError::Authentication)
+        );
+
+        assert_eq!(
+            keyring.decrypt("enc:v2:short"),
+            Err(EncryptionError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_blind_index() {
+        let old = keyring(&format!("1:{KEY_1}"));
+        let rotated = keyring(&format!("2:{KEY_2},1:{KEY_1}"));
+
+        let index = old.blind_index("google-oauth2|123");
+
+        assert_eq!(index, old.blind_index("google-oauth2|123"));
+        assert_ne!(index, old.blind_index("google-oauth2|124"));
+        assert!(!index.contains("google"));
+
+        // keyed, and looked up under every key while rotating
+        assert_ne!(rotated.blind_index("google-oauth2|123"), index);
+        assert_eq!(
+            rotated.blind_indexes("google-oauth2|123"),
+            vec![rotated.blind_index("google-oauth2|123"), index]
+        );
+    }
+}
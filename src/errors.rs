@@ -1,23 +1,43 @@
 use axum::{
     body::Body,
-    http::{Response, StatusCode},
+    http::{header, Response, StatusCode},
     response::IntoResponse,
     Json,
 };
-use serde::Serialize;
-use serde_json::json;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::validation::FieldError;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
 
 #[derive(Debug, Error)]
 pub enum ServerError {
     #[error("not found")]
     NotFound,
-    #[error("invalid uuid error")]
-    InvalidUUID(anyhow::Error),
+    #[error("method not allowed")]
+    MethodNotAllowed,
+    #[error("invalid cursor error")]
+    InvalidCursor(anyhow::Error),
     #[error("required body parameter")]
     RequiredBodyParameter,
     #[error("bad request")]
     BadReqest,
+    #[error("invalid body")]
+    InvalidBody(anyhow::Error),
+    #[error("unsupported media type")]
+    UnsupportedMediaType,
+    #[error("unsupported media type")]
+    UnsupportedMediaTypeReason(anyhow::Error),
+    #[error("validation failed")]
+    Validation(Vec<FieldError>),
+    #[error("conflict")]
+    Conflict,
+    #[error("conflict")]
+    ConflictReason(anyhow::Error),
+    #[error("precondition failed")]
+    PreconditionFailed,
     #[error("internal error")]
     Internal(anyhow::Error),
     #[error("unauthenticated_reason")]
@@ -34,9 +54,17 @@ impl ServerError {
     pub fn code(&self) -> String {
         match self {
             Self::NotFound => "not_found".to_owned(),
+            Self::MethodNotAllowed => "method_not_allowed".to_owned(),
             Self::BadReqest => "bad_request".to_owned(),
+            Self::Conflict => "conflict".to_owned(),
+            Self::ConflictReason(_) => "conflict".to_owned(),
+            Self::PreconditionFailed => "precondition_failed".to_owned(),
             Self::Internal(_) => "internal".to_owned(),
-            Self::InvalidUUID(_) => "invalid_uuid".to_owned(),
+            Self::InvalidBody(_) => "invalid_body".to_owned(),
+            Self::UnsupportedMediaType => "unsupported_media_type".to_owned(),
+            Self::UnsupportedMediaTypeReason(_) => "unsupported_media_type".to_owned(),
+            Self::Validation(_) => "validation_failed".to_owned(),
+            Self::InvalidCursor(_) => "invalid_cursor".to_owned(),
             Self::RequiredBodyParameter => "required_body_param".to_owned(),
             Self::UnauthenticatedReason(_) => "unauthenticated".to_owned(),
             Self::Unauthenticated => "unauthenticated".to_owned(),
@@ -44,12 +72,59 @@ impl ServerError {
             Self::UnauthorizedReason(_) => "unauthorized".to_owned(),
         }
     }
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidBody(_) => StatusCode::BAD_REQUEST,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::UnsupportedMediaTypeReason(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InvalidCursor(_) => StatusCode::BAD_REQUEST,
+            Self::BadReqest => StatusCode::BAD_REQUEST,
+            Self::Conflict => StatusCode::CONFLICT,
+            Self::ConflictReason(_) => StatusCode::CONFLICT,
+            Self::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Self::RequiredBodyParameter => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Self::UnauthenticatedReason(_) => StatusCode::UNAUTHORIZED,
+            Self::Unauthorized => StatusCode::FORBIDDEN,
+            Self::UnauthorizedReason(_) => StatusCode::FORBIDDEN,
+        }
+    }
+    // internal errors are only logged, their context is never sent to clients
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            Self::InvalidBody(err)
+            | Self::InvalidCursor(err)
+            | Self::UnsupportedMediaTypeReason(err)
+            | Self::ConflictReason(err)
+            | Self::UnauthenticatedReason(err)
+            | Self::UnauthorizedReason(err) => Some(err.to_string()),
+            _ => None,
+        }
+    }
+    pub fn field_errors(&self) -> Option<Vec<FieldError>> {
+        match self {
+            Self::Validation(errors) => Some(errors.to_owned()),
+            _ => None,
+        }
+    }
     pub fn message(&self) -> String {
         match self {
             Self::NotFound => "record not found".to_owned(),
+            Self::MethodNotAllowed => "method not allowed".to_owned(),
             Self::BadReqest => "bad request".to_owned(),
+            Self::Conflict => "record already exists".to_owned(),
+            Self::ConflictReason(_) => "conflict".to_owned(),
+            Self::PreconditionFailed => "record has changed".to_owned(),
             Self::Internal(_) => "internal".to_owned(),
-            Self::InvalidUUID(_) => "invalid uuid".to_owned(),
+            Self::InvalidBody(_) => "invalid body".to_owned(),
+            Self::UnsupportedMediaType => "unsupported media type".to_owned(),
+            Self::UnsupportedMediaTypeReason(_) => "unsupported media type".to_owned(),
+            Self::Validation(_) => "validation failed".to_owned(),
+            Self::InvalidCursor(_) => "invalid cursor".to_owned(),
             Self::RequiredBodyParameter => "required body parameter".to_owned(),
             Self::UnauthenticatedReason(_) => "unauthenticated".to_owned(),
             Self::Unauthenticated => "unauthenticated".to_owned(),
@@ -59,31 +134,62 @@ impl ServerError {
     }
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    code: String,
-    message: String,
+// an RFC 7807 problem details body, `instance` and `request_id` are filled in
+// by the problem details middleware which has access to the request
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+}
+
+impl Problem {
+    pub fn into_response(self) -> Response<Body> {
+        let status_code =
+            StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (
+            status_code,
+            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+            Json(self.clone()),
+        )
+            .into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
 }
 
 impl IntoResponse for ServerError {
     fn into_response(self) -> Response<Body> {
-        let body = Json(json!({
-            "error": self.code(),
-            "message": self.message(),
-        }));
+        let status_code = self.status();
 
-        let status_code = match self {
-            Self::NotFound => StatusCode::BAD_REQUEST,
-            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::InvalidUUID(_) => StatusCode::BAD_REQUEST,
-            Self::BadReqest => StatusCode::BAD_REQUEST,
-            Self::RequiredBodyParameter => StatusCode::BAD_REQUEST,
-            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
-            Self::UnauthenticatedReason(_) => StatusCode::UNAUTHORIZED,
-            Self::Unauthorized => StatusCode::UNAUTHORIZED,
-            Self::UnauthorizedReason(_) => StatusCode::UNAUTHORIZED,
-        };
+        if let Self::Internal(err) = &self {
+            tracing::error!(
+                error = ?err,
+                backtrace = %err.backtrace(),
+                "internal error"
+            );
+        }
 
-        (status_code, body).into_response()
+        Problem {
+            problem_type: format!("/problems/{}", self.code().replace('_', "-")),
+            title: self.message(),
+            status: status_code.as_u16(),
+            detail: self.detail(),
+            instance: None,
+            code: self.code(),
+            request_id: None,
+            errors: self.field_errors(),
+        }
+        .into_response()
     }
 }
@@ -1,8 +1,19 @@
 mod authentication;
 mod authorization;
+pub mod cors;
+mod extractors;
+mod group_users;
+mod groups;
 mod health;
+mod metrics;
+mod openapi;
+mod pagination;
+mod preconditions;
+mod problem_details;
 mod routes;
 mod users;
 
+#[cfg(test)]
 pub use self::routes::router;
+pub use self::routes::router_with_config;
 pub use self::routes::AppState;
@@ -1,19 +1,30 @@
 use anyhow::anyhow;
 use axum::{body::Body, extract::Request, http::Response, middleware::Next};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
 use uuid::Uuid;
 
 use crate::{
-    errors,
-    models::{self, user::Entity as User},
+    authentication::Claims,
+    authorization, encryption, errors,
+    models::{self, group_user::Entity as GroupUser},
+    validation,
 };
 
 pub async fn fetch_user_by_auth_id(
     conn: &DatabaseConnection,
     auth0_id: &str,
 ) -> Result<Option<models::user::Model>, errors::ServerError> {
-    User::find()
-        .filter(models::user::Column::Auth0Id.eq(auth0_id.to_owned()))
+    // rows written before encryption was enabled still hold the plaintext,
+    // until rotate-keys encrypts them
+    let mut condition = Condition::any().add(models::user::Column::Auth0Id.eq(auth0_id.to_owned()));
+
+    if let Some(keyring) = encryption::keyring() {
+        condition = condition
+            .add(models::user::Column::Auth0IdIndex.is_in(keyring.blind_indexes(auth0_id)));
+    }
+
+    models::user::find_active()
+        .filter(condition)
         .one(conn)
         .await
         .map_err(|err| errors::ServerError::Internal(anyhow!(err)))
@@ -23,12 +34,57 @@ pub async fn fetch_user_by_user_id(
     conn: &DatabaseConnection,
     user_id: Uuid,
 ) -> Result<Option<models::user::Model>, errors::ServerError> {
-    User::find_by_id(user_id)
+    models::user::find_active()
+        .filter(models::user::Column::UserId.eq(user_id))
         .one(conn)
         .await
         .map_err(|err| errors::ServerError::Internal(anyhow!(err)))
 }
 
+pub async fn fetch_actor(
+    conn: &DatabaseConnection,
+    claims: &Claims,
+) -> Result<authorization::User, errors::ServerError> {
+    let user = fetch_user_by_auth_id(conn, claims.sub.as_str())
+        .await?
+        .ok_or(errors::ServerError::Unauthorized)?;
+
+    Ok(authorization::User {
+        user_id: user.user_id.to_owned(),
+        role: user.role.to_owned(),
+    })
+}
+
+// resolves a user_id path parameter, where "me" refers to the actor
+pub fn parse_user_id(
+    user_id: &str,
+    actor: &authorization::User,
+) -> Result<Uuid, errors::ServerError> {
+    match user_id {
+        "me" => Ok(actor.user_id.to_owned()),
+        _ => validation::parse_uuid("user_id", user_id),
+    }
+}
+
+pub async fn fetch_membership(
+    conn: &DatabaseConnection,
+    group_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<authorization::GroupUser>, errors::ServerError> {
+    let group_user = GroupUser::find()
+        .filter(models::group_user::Column::GroupId.eq(group_id))
+        .filter(models::group_user::Column::UserId.eq(user_id))
+        .one(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok(group_user.map(|group_user| authorization::GroupUser {
+        group_id: group_user.group_id.to_owned(),
+        user_id: group_user.user_id.to_owned(),
+        role: group_user.role.to_owned(),
+    }))
+}
+
 pub async fn can_list_users(
     // State(state): State<AppState>,
     // Extension(claims): Extension<Claims>,
@@ -0,0 +1,100 @@
+#[path = "cors_test.rs"]
+#[cfg(test)]
+mod cors_test;
+
+use std::str::FromStr;
+
+use axum::http::{header, request::Parts, HeaderName, HeaderValue};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+use crate::telemetry::REQUEST_ID_HEADER;
+
+// an entry of ALLOWED_ORIGINS: "*", an exact origin such as
+// "https://app.example.com", or a wildcard such as "https://*.example.com"
+// matching any subdomain but not the domain itself
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllowedOrigin {
+    Any,
+    Exact(String),
+    Subdomains { scheme: String, suffix: String },
+}
+
+impl AllowedOrigin {
+    pub fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+
+        match self {
+            AllowedOrigin::Any => true,
+            AllowedOrigin::Exact(allowed) => origin == *allowed,
+            AllowedOrigin::Subdomains { scheme, suffix } => origin
+                .strip_prefix(scheme.as_str())
+                .and_then(|host| host.strip_suffix(suffix.as_str()))
+                .is_some_and(|subdomain| {
+                    !subdomain.is_empty()
+                        && !subdomain.starts_with('.')
+                        && !subdomain.ends_with('.')
+                        && subdomain
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                }),
+        }
+    }
+}
+
+impl FromStr for AllowedOrigin {
+    type Err = String;
+
+    fn from_str(origin: &str) -> Result<AllowedOrigin, String> {
+        if origin == "*" {
+            return Ok(AllowedOrigin::Any);
+        }
+
+        let origin = origin.to_ascii_lowercase();
+
+        let (scheme, host) = ["http://", "https://"]
+            .into_iter()
+            .find_map(|scheme| origin.strip_prefix(scheme).map(|host| (scheme, host)))
+            .ok_or("expected an http:// or https:// origin")?;
+
+        if host.is_empty() || host.contains(['/', '?', '#', '@']) {
+            return Err("expected a scheme and host without a path".to_owned());
+        }
+
+        match host.strip_prefix('*') {
+            Some(suffix) if suffix.starts_with('.') && !suffix[1..].contains('*') => {
+                Ok(AllowedOrigin::Subdomains {
+                    scheme: scheme.to_owned(),
+                    suffix: suffix.to_owned(),
+                })
+            }
+            None if !host.contains('*') => Ok(AllowedOrigin::Exact(origin)),
+            _ => Err(
+                "wildcards are only supported as the leading label, e.g. https://*.example.com"
+                    .to_owned(),
+            ),
+        }
+    }
+}
+
+// answers preflight requests before they reach the authentication middleware
+// and adds the allow headers to every response for an allowed origin
+pub fn layer(config: &CorsConfig) -> CorsLayer {
+    let allowed_origins = config.allowed_origins.clone();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(
+            move |origin: &HeaderValue, _: &Parts| {
+                origin.to_str().is_ok_and(|origin| {
+                    allowed_origins
+                        .iter()
+                        .any(|allowed| allowed.matches(origin))
+                })
+            },
+        ))
+        .allow_methods(config.allowed_methods.clone())
+        .allow_headers(config.allowed_headers.clone())
+        .expose_headers([header::ETAG, HeaderName::from_static(REQUEST_ID_HEADER)])
+        .allow_credentials(config.allow_credentials)
+        .max_age(config.max_age)
+}
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+
+    use crate::{
+        authentication, authorization,
+        config::{CorsConfig, DocsConfig},
+        handlers::{cors::AllowedOrigin, router_with_config, AppState},
+    };
+
+    // authentication and authorization mocks have no expectations, so any
+    // request reaching them fails the test
+    fn get_router(allow_credentials: bool) -> axum::Router {
+        let cors = CorsConfig {
+            allowed_origins: vec![
+                "https://app.example.com".parse().unwrap(),
+                "https://*.example.org".parse().unwrap(),
+            ],
+            allow_credentials,
+            max_age: Duration::from_secs(300),
+            ..CorsConfig::default()
+        };
+
+        router_with_config(
+            AppState {
+                conn: Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection()),
+                authentication: Arc::new(authentication::MockIAuthentication::new()),
+                authorization: Arc::new(authorization::MockIAuthorization::new()),
+                readiness: Default::default(),
+            },
+            &cors,
+            &DocsConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_allowed_origin() {
+        let cases = [
+            ("*", "https://anything.example.net", true),
+            ("https://app.example.com", "https://app.example.com", true),
+            ("https://app.example.com", "https://APP.example.com", true),
+            ("https://app.example.com", "http://app.example.com", false),
+            (
+                "https://app.example.com",
+                "https://app.example.com:8443",
+                false,
+            ),
+            ("https://*.example.com", "https://app.example.com", true),
+            ("https://*.example.com", "https://a.b.example.com", true),
+            ("https://*.example.com", "https://example.com", false),
+            ("https://*.example.com", "https://evil-example.com", false),
+            (
+                "https://*.example.com",
+                "https://example.com.evil.net",
+                false,
+            ),
+            ("https://*.example.com", "https://a.example.com:8443", false),
+            ("http://*.localhost:3000", "http://app.localhost:3000", true),
+        ];
+
+        for (allowed, origin, expected) in cases {
+            let allowed: AllowedOrigin = allowed.parse().unwrap();
+            assert_eq!(allowed.matches(origin), expected, "{allowed:?} {origin}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_allowed_origin() {
+        for origin in [
+            "app.example.com",
+            "ftp://app.example.com",
+            "https://",
+            "https://app.example.com/path",
+            "https://app.*.example.com",
+            "https://*example.com",
+            "https://*.*.example.com",
+        ] {
+            assert!(origin.parse::<AllowedOrigin>().is_err(), "{origin}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preflight_authenticated_route() {
+        let response = get_router(true)
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/users/me")
+                    .header(header::ORIGIN, "https://app.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+                    .header(
+                        header::ACCESS_CONTROL_REQUEST_HEADERS,
+                        "authorization,content-type",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "300");
+
+        let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("PUT"), "{methods}");
+
+        let allowed_headers = headers[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap();
+        assert!(
+            allowed_headers.contains("authorization"),
+            "{allowed_headers}"
+        );
+        assert!(
+            allowed_headers.contains("content-type"),
+            "{allowed_headers}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preflight_disallowed_origin() {
+        let response = get_router(false)
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/users")
+                    .header(header::ORIGIN, "https://example.org")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    // browsers can only read an error response when it carries the allow headers
+    #[tokio::test]
+    async fn test_error_response_allows_origin() {
+        let response = get_router(false)
+            .oneshot(
+                Request::builder()
+                    .uri("/users")
+                    .header(header::ORIGIN, "https://admin.example.org")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://admin.example.org"
+        );
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_EXPOSE_HEADERS],
+            "etag,x-request-id"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::anyhow;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::{header, request::Parts, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::error::Category;
+
+use crate::errors::{self, ServerError};
+use crate::validation::{FieldError, Validate};
+
+// axum::Json, except rejections and validation failures are reported as
+// problem details with the failing fields listed
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ServerError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        // a merge patch sent here would be read as a full replacement
+        if is_merge_patch_content_type(req.headers()) {
+            return Err(errors::ServerError::UnsupportedMediaTypeReason(anyhow!(
+                "{MERGE_PATCH_CONTENT_TYPE} is only accepted by PATCH"
+            )));
+        }
+        if !is_json_content_type(req.headers()) {
+            return Err(errors::ServerError::UnsupportedMediaType);
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|err| errors::ServerError::InvalidBody(anyhow!(err.body_text())))?;
+
+        let value: T = deserialize_json(&bytes)?;
+        value.validate()?;
+
+        Ok(Json(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+// an RFC 7396 merge patch, reported like Json but only accepted as
+// application/merge-patch+json; fields are deserialized with `present` to
+// tell a field set to null from one that was left out
+pub struct MergePatch<T>(pub T);
+
+impl<T, S> FromRequest<S> for MergePatch<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ServerError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_merge_patch_content_type(req.headers()) {
+            return Err(errors::ServerError::UnsupportedMediaType);
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|err| errors::ServerError::InvalidBody(anyhow!(err.body_text())))?;
+
+        let value: T = deserialize_json(&bytes)?;
+        value.validate()?;
+
+        Ok(MergePatch(value))
+    }
+}
+
+// Some(None) for a field set to null, used with #[serde(default)] so a field
+// that was left out stays None
+pub fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// axum::extract::Query with the same error reporting as Json
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ServerError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+
+        let value: T = serde_path_to_error::deserialize(deserializer).map_err(|err| {
+            let field = err.path().to_string();
+            errors::ServerError::Validation(vec![field_error(&field, err.inner().to_string())])
+        })?;
+        value.validate()?;
+
+        Ok(Query(value))
+    }
+}
+
+// accepts application/json as well as structured suffixes like application/problem+json
+fn is_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    mime == "application/json" || (mime.starts_with("application/") && mime.ends_with("+json"))
+}
+
+fn is_merge_patch_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(MERGE_PATCH_CONTENT_TYPE))
+}
+
+fn deserialize_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ServerError> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+
+    serde_path_to_error::deserialize(deserializer).map_err(|err| {
+        let field = err.path().to_string();
+        let inner = err.into_inner();
+        match inner.classify() {
+            Category::Data => {
+                errors::ServerError::Validation(vec![field_error(&field, inner.to_string())])
+            }
+            _ => errors::ServerError::InvalidBody(anyhow!(inner)),
+        }
+    })
+}
+
+// serde reports missing fields against the parent, so the name is pulled out
+// of the message to point at the field itself
+fn field_error(path: &str, message: String) -> FieldError {
+    if let Some(missing) = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split('`').next())
+    {
+        let field = match path {
+            "." | "" => missing.to_owned(),
+            _ => format!("{path}.{missing}"),
+        };
+        return FieldError::new(&field, "required", "is required");
+    }
+
+    FieldError::new(path, "invalid_type", message)
+}
@@ -0,0 +1,434 @@
+#[path = "group_users_test.rs"]
+#[cfg(test)]
+mod group_users_test;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Extension;
+
+use sea_orm::entity::*;
+use sea_orm::{
+    DatabaseTransaction, EntityTrait, ModelTrait, QueryFilter, QuerySelect, RelationTrait, SqlErr,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::authentication::Claims;
+use crate::errors::{self, Problem, ServerError};
+use crate::models::group::Entity as Group;
+use crate::models::group_user::Entity as GroupUser;
+use crate::models::user::Entity as User;
+use crate::validation::{Validate, Validator};
+use crate::{authorization, models};
+use anyhow::anyhow;
+
+use super::authorization::{fetch_actor, fetch_membership, fetch_user_by_user_id, parse_user_id};
+use super::extractors::Json;
+use super::groups::{fetch_group, parse_group_id, GroupResponse};
+use super::users::UserResponse;
+use super::AppState;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GroupUserResponse {
+    group_user_id: Uuid,
+    group_id: Uuid,
+    user_id: Uuid,
+    role: String,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+    updated_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CreateGroupUser {
+    user_id: Uuid,
+    role: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ModifyGroupUser {
+    role: String,
+}
+
+impl Validate for CreateGroupUser {
+    fn validate(&self) -> Result<(), ServerError> {
+        let mut validator = Validator::new();
+        if let Some(role) = &self.role {
+            validator.one_of("role", role.as_str(), &authorization::GROUP_ROLES);
+        }
+        validator.finish()
+    }
+}
+
+impl Validate for ModifyGroupUser {
+    fn validate(&self) -> Result<(), ServerError> {
+        Validator::new()
+            .one_of("role", self.role.as_str(), &authorization::GROUP_ROLES)
+            .finish()
+    }
+}
+
+impl From<&models::group_user::Model> for GroupUserResponse {
+    fn from(group_user: &models::group_user::Model) -> Self {
+        GroupUserResponse {
+            group_user_id: group_user.group_user_id.to_owned(),
+            group_id: group_user.group_id.to_owned(),
+            user_id: group_user.user_id.to_owned(),
+            role: group_user.role.to_owned(),
+            created_at: group_user.created_at.to_owned(),
+            updated_at: group_user.updated_at.to_owned(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{group_id}/users",
+    tag = "group users",
+    params(("group_id" = String, Path, description = "Group id")),
+    responses(
+        (status = 200, description = "Members of the group", body = Vec<UserResponse>),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn list_group_users(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let group_id = parse_group_id(group_id.as_str())?;
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let membership = fetch_membership(conn, group_id, actor.user_id).await?;
+
+    authorization
+        .can_list_group_users(actor, membership)
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let group = fetch_group(conn, group_id).await?;
+
+    let users: Vec<models::user::Model> = group
+        .find_related(User)
+        .filter(models::user::Column::DeletedAt.is_null())
+        .all(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok(Json(
+        users
+            .iter()
+            .map(UserResponse::from)
+            .collect::<Vec<UserResponse>>(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/groups/{group_id}/users",
+    tag = "group users",
+    params(("group_id" = String, Path, description = "Group id")),
+    request_body = CreateGroupUser,
+    responses(
+        (status = 201, description = "The created membership", body = GroupUserResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The user is already a member of the group", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn create_group_user(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(body): Json<CreateGroupUser>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let group_id = parse_group_id(group_id.as_str())?;
+
+    let role = body
+        .role
+        .unwrap_or_else(|| authorization::GROUP_ROLE_MEMBER.to_owned());
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let membership = fetch_membership(conn, group_id, actor.user_id).await?;
+
+    authorization
+        .can_add_member(actor, membership, role.to_owned())
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    fetch_group(conn, group_id).await?;
+
+    fetch_user_by_user_id(conn, body.user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    let group_user_found = GroupUser::find()
+        .filter(models::group_user::Column::GroupId.eq(group_id))
+        .filter(models::group_user::Column::UserId.eq(body.user_id))
+        .one(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    if group_user_found.is_some() {
+        return Err(errors::ServerError::Conflict);
+    }
+
+    let group_user: models::group_user::Model = models::group_user::ActiveModel {
+        group_user_id: NotSet,
+        group_id: Set(group_id),
+        user_id: Set(body.user_id),
+        role: Set(role),
+        created_at: NotSet,
+        updated_at: NotSet,
+    }
+    .insert(conn)
+    .await
+    .map_err(|err| match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => errors::ServerError::Conflict,
+        _ => errors::ServerError::Internal(anyhow!(err)),
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(GroupUserResponse::from(&group_user)),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/groups/{group_id}/users/{user_id}",
+    tag = "group users",
+    params(("group_id" = String, Path, description = "Group id"), ("user_id" = String, Path, description = "User id, or \"me\" for the authenticated user")),
+    request_body = ModifyGroupUser,
+    responses(
+        (status = 200, description = "The updated membership", body = GroupUserResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The member is the group's last owner", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn modify_group_user(
+    State(state): State<AppState>,
+    Path((group_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+    Json(body): Json<ModifyGroupUser>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let group_id = parse_group_id(group_id.as_str())?;
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = parse_user_id(user_id.as_str(), &actor)?;
+    let membership = fetch_membership(conn, group_id, actor.user_id).await?;
+
+    let group_user = GroupUser::find()
+        .filter(models::group_user::Column::GroupId.eq(group_id))
+        .filter(models::group_user::Column::UserId.eq(user_id))
+        .one(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    authorization
+        .can_modify_member(
+            actor,
+            membership,
+            authorization::GroupUser {
+                group_id: group_user.group_id.to_owned(),
+                user_id: group_user.user_id.to_owned(),
+                role: group_user.role.to_owned(),
+            },
+            body.role.to_owned(),
+        )
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    // memberships of deleted users are kept for restoring but hidden
+    fetch_user_by_user_id(conn, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    let txn = conn
+        .begin()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    if group_user.role == authorization::GROUP_ROLE_OWNER
+        && body.role != authorization::GROUP_ROLE_OWNER
+    {
+        ensure_other_owner(&txn, group_id, user_id).await?;
+    }
+
+    let mut group_user: models::group_user::ActiveModel = group_user.into();
+    group_user.role = Set(body.role);
+
+    let group_user_updated: models::group_user::Model = group_user
+        .update(&txn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    txn.commit()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok(Json(GroupUserResponse::from(&group_user_updated)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/groups/{group_id}/users/{user_id}",
+    tag = "group users",
+    params(("group_id" = String, Path, description = "Group id"), ("user_id" = String, Path, description = "User id, or \"me\" for the authenticated user")),
+    responses(
+        (status = 204, description = "The membership was deleted"),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The member is the group's last owner", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn delete_group_user(
+    State(state): State<AppState>,
+    Path((group_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let group_id = parse_group_id(group_id.as_str())?;
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = parse_user_id(user_id.as_str(), &actor)?;
+    let membership = fetch_membership(conn, group_id, actor.user_id).await?;
+
+    let member = fetch_membership(conn, group_id, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    let is_owner = member.role == authorization::GROUP_ROLE_OWNER;
+
+    authorization
+        .can_remove_member(actor, membership, member)
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    fetch_user_by_user_id(conn, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    let txn = conn
+        .begin()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    if is_owner {
+        ensure_other_owner(&txn, group_id, user_id).await?;
+    }
+
+    GroupUser::delete_many()
+        .filter(models::group_user::Column::GroupId.eq(group_id))
+        .filter(models::group_user::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    txn.commit()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// fails unless the group has an active owner besides `user_id`, so an owner
+// can't step down, be removed or be deleted when that would leave the group
+// without one; the owners are locked until the transaction ends so two owners
+// can't both step down at once
+pub(super) async fn ensure_other_owner(
+    txn: &DatabaseTransaction,
+    group_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), ServerError> {
+    let owners: Vec<models::group_user::Model> = GroupUser::find()
+        .join(
+            sea_orm::JoinType::InnerJoin,
+            models::group_user::Relation::User.def(),
+        )
+        .filter(models::group_user::Column::GroupId.eq(group_id))
+        .filter(models::group_user::Column::Role.eq(authorization::GROUP_ROLE_OWNER))
+        .filter(models::user::Column::DeletedAt.is_null())
+        .lock_exclusive()
+        .all(txn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    if owners.iter().all(|owner| owner.user_id == user_id) {
+        return Err(errors::ServerError::ConflictReason(anyhow!(
+            "a group must keep at least one owner"
+        )));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/groups",
+    tag = "group users",
+    params(("user_id" = String, Path, description = "User id, or \"me\" for the authenticated user")),
+    responses(
+        (status = 200, description = "Groups the user is a member of", body = Vec<GroupResponse>),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn list_user_groups(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = parse_user_id(user_id.as_str(), &actor)?;
+
+    authorization
+        .can_list_user_groups(actor, user_id.to_owned())
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    fetch_user_by_user_id(conn, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    let groups: Vec<models::group::Model> = Group::find()
+        .join(
+            sea_orm::JoinType::InnerJoin,
+            models::group::Relation::GroupUser.def(),
+        )
+        .filter(models::group_user::Column::UserId.eq(user_id))
+        .all(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok(Json(
+        groups
+            .iter()
+            .map(GroupResponse::from)
+            .collect::<Vec<GroupResponse>>(),
+    ))
+}
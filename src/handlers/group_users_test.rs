@@ -0,0 +1,650 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::{
+        authorization,
+        errors::Problem,
+        handlers::{group_users::GroupUserResponse, router, AppState},
+        models,
+        test_utils::{self, get_group_db, get_group_user_db, get_user_db},
+    };
+
+    #[tokio::test]
+    async fn test_list_group_users() {
+        let actor_db = get_user_db("admin");
+        let member_db = get_user_db("user");
+        let group_db = get_group_db("group");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .append_query_results(vec![vec![member_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/groups/{}/users", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.len(), 1);
+        assert_eq!(body[0]["user_id"], member_db.user_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_list_group_users_excludes_deleted_members() {
+        let actor_db = get_user_db("admin");
+        let group_db = get_group_db("group");
+
+        let conn = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![actor_db.clone()]])
+                .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+                .append_query_results(vec![vec![group_db.clone()]])
+                .append_query_results(vec![Vec::<models::user::Model>::new()])
+                .into_connection(),
+        );
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: conn.clone(),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/groups/{}/users", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // the members are queried last, without deleted users
+        let log = Arc::try_unwrap(conn).unwrap().into_transaction_log();
+        let members = format!("{:?}", log.last().unwrap());
+        assert!(
+            members.contains(r#"\"users\".\"deleted_at\" IS NULL"#),
+            "{members}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_user_groups_deleted_user() {
+        let actor_db = get_user_db("admin");
+
+        let conn = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![actor_db.clone()]])
+                .append_query_results(vec![Vec::<models::user::Model>::new()])
+                .into_connection(),
+        );
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: conn.clone(),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/users/{}/groups", Uuid::new_v4()))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_create_group_user() {
+        let actor_db = get_user_db("admin");
+        let member_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let group_user_db = get_group_user_db(group_db.group_id, member_db.user_id, "member");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .append_query_results(vec![vec![member_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "user_id": member_db.user_id,
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/groups/{}/users", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: GroupUserResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.group_user_id, group_user_db.group_user_id);
+        assert_eq!(body.group_id, group_db.group_id);
+        assert_eq!(body.user_id, member_db.user_id);
+    }
+
+    #[tokio::test]
+    async fn test_create_group_user_duplicate() {
+        let actor_db = get_user_db("admin");
+        let member_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let group_user_db = get_group_user_db(group_db.group_id, member_db.user_id, "member");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .append_query_results(vec![vec![member_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "user_id": member_db.user_id,
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/groups/{}/users", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_delete_group_user() {
+        let actor_db = get_user_db("user");
+        let group_id = Uuid::new_v4();
+        let group_user_db = get_group_user_db(group_id, actor_db.user_id, "member");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(format!("/groups/{}/users/me", group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_delete_group_user_not_found() {
+        let actor_db = get_user_db("admin");
+        let group_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(format!("/groups/{}/users/{}", group_id, user_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_list_user_groups() {
+        let actor_db = get_user_db("user");
+        let group_db = get_group_db("group");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/users/me/groups")
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.len(), 1);
+        assert_eq!(body[0]["group_id"], group_db.group_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_modify_group_user() {
+        let actor_db = get_user_db("user");
+        let member_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let membership_db = get_group_user_db(group_db.group_id, actor_db.user_id, "owner");
+        let group_user_db = get_group_user_db(group_db.group_id, member_db.user_id, "member");
+        let group_user_db_modified = models::group_user::Model {
+            role: "admin".to_owned(),
+            ..group_user_db.clone()
+        };
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .append_query_results(vec![vec![member_db.clone()]])
+            .append_query_results(vec![vec![group_user_db_modified.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "role": "admin",
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::PUT)
+                    .uri(format!(
+                        "/groups/{}/users/{}",
+                        group_db.group_id, member_db.user_id
+                    ))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: GroupUserResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.user_id, member_db.user_id);
+        assert_eq!(body.role, "admin");
+    }
+
+    #[tokio::test]
+    async fn test_create_group_user_owner_by_group_admin() {
+        let actor_db = get_user_db("user");
+        let member_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let membership_db = get_group_user_db(group_db.group_id, actor_db.user_id, "admin");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "user_id": member_db.user_id,
+            "role": "owner",
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(format!("/groups/{}/users", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_modify_group_user_last_owner() {
+        let actor_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let membership_db = get_group_user_db(group_db.group_id, actor_db.user_id, "owner");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "role": "member",
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::PUT)
+                    .uri(format!("/groups/{}/users/me", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body.detail,
+            Some("a group must keep at least one owner".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_modify_group_user_with_another_owner() {
+        let actor_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let membership_db = get_group_user_db(group_db.group_id, actor_db.user_id, "owner");
+        let other_owner_db = get_group_user_db(group_db.group_id, Uuid::new_v4(), "owner");
+        let membership_db_modified = models::group_user::Model {
+            role: "member".to_owned(),
+            ..membership_db.clone()
+        };
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone(), other_owner_db.clone()]])
+            .append_query_results(vec![vec![membership_db_modified.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "role": "member",
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::PUT)
+                    .uri(format!("/groups/{}/users/me", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_delete_group_user_last_owner() {
+        let actor_db = get_user_db("user");
+        let group_id = Uuid::new_v4();
+        let membership_db = get_group_user_db(group_id, actor_db.user_id, "owner");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .append_query_results(vec![vec![actor_db.clone()]])
+            .append_query_results(vec![vec![membership_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(format!("/groups/{}/users/me", group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,373 @@
+#[path = "groups_test.rs"]
+#[cfg(test)]
+mod groups_test;
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::Extension;
+
+use sea_orm::entity::*;
+use sea_orm::{DbErr, EntityTrait, QueryFilter, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::authentication::Claims;
+use crate::errors::{self, Problem, ServerError};
+use crate::models::group::Entity as Group;
+use crate::models::group_user::Entity as GroupUser;
+use crate::validation::{self, Validate, Validator};
+use crate::{authorization, models};
+use anyhow::anyhow;
+
+use super::authorization::{fetch_actor, fetch_membership};
+use super::extractors::Json;
+use super::preconditions::{etag, with_etag, Preconditions};
+use super::AppState;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GroupResponse {
+    group_id: Uuid,
+    name: String,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+    updated_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CreateGroup {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ModifyGroup {
+    name: Option<String>,
+}
+
+impl Validate for CreateGroup {
+    fn validate(&self) -> Result<(), ServerError> {
+        Validator::new()
+            .text("name", self.name.as_str(), validation::MAX_NAME_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for ModifyGroup {
+    fn validate(&self) -> Result<(), ServerError> {
+        Validator::new()
+            .optional_text("name", self.name.as_deref(), validation::MAX_NAME_LENGTH)
+            .finish()
+    }
+}
+
+impl From<&models::group::Model> for GroupResponse {
+    fn from(group: &models::group::Model) -> Self {
+        GroupResponse {
+            group_id: group.group_id.to_owned(),
+            name: group.name.to_owned(),
+            created_at: group.created_at.to_owned(),
+            updated_at: group.updated_at.to_owned(),
+        }
+    }
+}
+
+pub fn parse_group_id(group_id: &str) -> Result<Uuid, ServerError> {
+    validation::parse_uuid("group_id", group_id)
+}
+
+pub async fn fetch_group(
+    conn: &sea_orm::DatabaseConnection,
+    group_id: Uuid,
+) -> Result<models::group::Model, ServerError> {
+    Group::find_by_id(group_id)
+        .one(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?
+        .ok_or(errors::ServerError::NotFound)
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups",
+    tag = "groups",
+    responses(
+        (status = 200, description = "All groups", body = Vec<GroupResponse>),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn list_groups(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let actor = fetch_actor(conn, &claims).await?;
+
+    authorization
+        .can_list_groups(actor)
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let groups: Vec<models::group::Model> = Group::find()
+        .all(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok(Json(
+        groups
+            .iter()
+            .map(GroupResponse::from)
+            .collect::<Vec<GroupResponse>>(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{group_id}",
+    tag = "groups",
+    params(
+        ("group_id" = String, Path, description = "Group id"),
+        ("If-None-Match" = Option<String>, Header, description = "ETags the client has, answered with 304 when one is current"),
+    ),
+    responses(
+        (status = 200, description = "The group", body = GroupResponse, headers(("ETag" = String, description = "The current version of the group"))),
+        (status = 304, description = "The group hasn't changed since the given ETag"),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn get_group(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    preconditions: Preconditions,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let group_id = parse_group_id(group_id.as_str())?;
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let membership = fetch_membership(conn, group_id, actor.user_id).await?;
+
+    authorization
+        .can_get_group(actor, membership)
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let group = fetch_group(conn, group_id).await?;
+
+    Ok(with_etag(
+        &preconditions,
+        group.version,
+        Json(GroupResponse::from(&group)),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/groups",
+    tag = "groups",
+    request_body = CreateGroup,
+    responses(
+        (status = 201, description = "The created group, owned by the actor", body = GroupResponse, headers(("ETag" = String, description = "The version of the group"))),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn create_group(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(body): Json<CreateGroup>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let actor = fetch_actor(conn, &claims).await?;
+
+    authorization
+        .can_create_group(actor.clone())
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let txn = conn
+        .begin()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    let group: models::group::Model = models::group::ActiveModel {
+        group_id: NotSet,
+        name: Set(body.name.to_owned()),
+        created_at: NotSet,
+        updated_at: NotSet,
+        version: NotSet,
+    }
+    .insert(&txn)
+    .await
+    .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    // the creator of a group becomes its owner
+    models::group_user::ActiveModel {
+        group_user_id: NotSet,
+        group_id: Set(group.group_id.to_owned()),
+        user_id: Set(actor.user_id.to_owned()),
+        role: Set(authorization::GROUP_ROLE_OWNER.to_owned()),
+        created_at: NotSet,
+        updated_at: NotSet,
+    }
+    .insert(&txn)
+    .await
+    .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    txn.commit()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::ETAG, etag(group.version))],
+        Json(GroupResponse::from(&group)),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/groups/{group_id}",
+    tag = "groups",
+    params(
+        ("group_id" = String, Path, description = "Group id"),
+        ("If-Match" = Option<String>, Header, description = "Only update the group if its ETag is one of these"),
+    ),
+    request_body = ModifyGroup,
+    responses(
+        (status = 200, description = "The updated group", body = GroupResponse, headers(("ETag" = String, description = "The new version of the group"))),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The group has changed since the given ETag", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn modify_group(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    preconditions: Preconditions,
+    Json(body): Json<ModifyGroup>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let group_id = parse_group_id(group_id.as_str())?;
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let membership = fetch_membership(conn, group_id, actor.user_id).await?;
+
+    authorization
+        .can_manage_group(actor, membership)
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let group = fetch_group(conn, group_id).await?;
+
+    preconditions.check_match(group.version)?;
+    let version = group.version;
+
+    let mut group: models::group::ActiveModel = group.into();
+
+    if let Some(name) = body.name {
+        group.name = Set(name);
+    }
+
+    // Entity::update skips the model's hooks, so stamp and bump here
+    let group = group
+        .before_save(conn, false)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    // guarded on the version that was read, so a concurrent update isn't
+    // silently overwritten
+    let group_updated: models::group::Model = Group::update(group)
+        .filter(models::group::Column::Version.eq(version))
+        .exec(conn)
+        .await
+        .map_err(|err| match err {
+            DbErr::RecordNotUpdated => errors::ServerError::PreconditionFailed,
+            _ => errors::ServerError::Internal(anyhow!(err)),
+        })?;
+
+    Ok((
+        [(header::ETAG, etag(group_updated.version))],
+        Json(GroupResponse::from(&group_updated)),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/groups/{group_id}",
+    tag = "groups",
+    params(
+        ("group_id" = String, Path, description = "Group id"),
+        ("If-Match" = Option<String>, Header, description = "Only delete the group if its ETag is one of these"),
+    ),
+    responses(
+        (status = 204, description = "The group and its memberships were deleted"),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The group has changed since the given ETag", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn delete_group(
+    State(state): State<AppState>,
+    Path(group_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    preconditions: Preconditions,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let group_id = parse_group_id(group_id.as_str())?;
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let membership = fetch_membership(conn, group_id, actor.user_id).await?;
+
+    authorization
+        .can_delete_group(actor, membership)
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let group = fetch_group(conn, group_id).await?;
+
+    preconditions.check_match(group.version)?;
+
+    let txn = conn
+        .begin()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    GroupUser::delete_many()
+        .filter(models::group_user::Column::GroupId.eq(group_id))
+        .exec(&txn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    let res = Group::delete_by_id(group_id)
+        .filter(models::group::Column::Version.eq(group.version))
+        .exec(&txn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    // updated or deleted since it was fetched, the memberships are kept as
+    // the transaction is rolled back
+    if res.rows_affected == 0 {
+        return Err(errors::ServerError::PreconditionFailed);
+    }
+
+    txn.commit()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,461 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::{
+        authorization,
+        errors::Problem,
+        handlers::{groups::GroupResponse, router, AppState},
+        models,
+        test_utils::{self, get_group_db, get_group_user_db, get_user_db},
+    };
+
+    #[tokio::test]
+    async fn test_get_group() {
+        let user_db = get_user_db("admin");
+        let group_db = get_group_db("group");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/groups/{}", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], "\"1\"");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: GroupResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.group_id, group_db.group_id);
+        assert_eq!(body.name, "group");
+        assert_eq!(body.created_at, group_db.created_at);
+        assert_eq!(body.updated_at, group_db.updated_at);
+    }
+
+    #[tokio::test]
+    async fn test_get_group_unauthorized() {
+        let user_db = get_user_db("user");
+        let group_db = get_group_db("group");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/groups/{}", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_list_groups() {
+        let user_db = get_user_db("admin");
+        let group_db_1 = get_group_db("group_1");
+        let group_db_2 = get_group_db("group_2");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![vec![group_db_1.clone(), group_db_2.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/groups")
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Vec<GroupResponse> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(body.len(), 2);
+        assert_eq!(body[0].group_id, group_db_1.group_id);
+        assert_eq!(body[0].name, "group_1");
+        assert_eq!(body[1].group_id, group_db_2.group_id);
+        assert_eq!(body[1].name, "group_2");
+    }
+
+    #[tokio::test]
+    async fn test_create_group() {
+        let user_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let group_user_db = get_group_user_db(group_db.group_id, user_db.user_id, "owner");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "name": "group",
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/groups")
+                    .header(default_auth_header, default_auth_header_value)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let group_resp = response.into_body().collect().await.unwrap().to_bytes();
+        let group_resp: GroupResponse = serde_json::from_slice(&group_resp).unwrap();
+
+        assert_eq!(group_resp.group_id, group_db.group_id);
+        assert_eq!(group_resp.name, group_db.name);
+        assert_eq!(group_resp.created_at, group_db.created_at);
+        assert_eq!(group_resp.updated_at, group_db.updated_at);
+    }
+
+    #[tokio::test]
+    async fn test_modify_group() {
+        let user_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let group_user_db = get_group_user_db(group_db.group_id, user_db.user_id, "admin");
+        let group_db_modified = models::group::Model {
+            name: "group_different".to_owned(),
+            version: 2,
+            ..group_db.clone()
+        };
+
+        let conn = Arc::new(
+            MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![user_db.clone()]])
+                .append_query_results(vec![vec![group_user_db.clone()]])
+                .append_query_results(vec![vec![group_db.clone()]])
+                .append_query_results(vec![vec![group_db_modified.clone()]])
+                .into_connection(),
+        );
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: conn.clone(),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "name": "group_different",
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::PUT)
+                    .uri(format!("/groups/{}", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header(header::IF_MATCH, "\"1\"")
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], "\"2\"");
+
+        let group_resp = response.into_body().collect().await.unwrap().to_bytes();
+        let group_resp: GroupResponse = serde_json::from_slice(&group_resp).unwrap();
+        assert_eq!(group_resp.group_id, group_db.group_id);
+        assert_eq!(group_resp.name, "group_different");
+
+        // the model's hooks run on the guarded update
+        let log = format!(
+            "{:?}",
+            Arc::try_unwrap(conn).unwrap().into_transaction_log()
+        );
+        assert!(log.contains(r#"\"updated_at\" ="#), "{log}");
+        assert!(log.contains(r#"\"version\" ="#), "{log}");
+    }
+
+    #[tokio::test]
+    async fn test_delete_group() {
+        let user_db = get_user_db("user");
+        let group_db = get_group_db("group");
+        let group_id = group_db.group_id;
+        let group_user_db = get_group_user_db(group_id, user_db.user_id, "owner");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+            ])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(format!("/groups/{}", group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_delete_group_as_group_admin() {
+        let user_db = get_user_db("user");
+        let group_id = Uuid::new_v4();
+        let group_user_db = get_group_user_db(group_id, user_db.user_id, "admin");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(format!("/groups/{}", group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_get_group_not_modified() {
+        let user_db = get_user_db("admin");
+        let group_db = get_group_db("group");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![Vec::<models::group_user::Model>::new()])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/groups/{}", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header(header::IF_NONE_MATCH, "\"1\"")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], "\"1\"");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_modify_group_stale_etag() {
+        let user_db = get_user_db("user");
+        let group_db = models::group::Model {
+            version: 2,
+            ..get_group_db("group")
+        };
+        let group_user_db = get_group_user_db(group_db.group_id, user_db.user_id, "admin");
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db.clone()]])
+            .append_query_results(vec![vec![group_user_db.clone()]])
+            .append_query_results(vec![vec![group_db.clone()]])
+            .into_connection();
+
+        let auth = test_utils::get_default_auth();
+        let authz = authorization::Authorization {};
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(auth),
+            authorization: Arc::from(authz),
+            readiness: Default::default(),
+        });
+
+        let body = serde_json::json!({
+            "name": "group_different",
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::PUT)
+                    .uri(format!("/groups/{}", group_db.group_id))
+                    .header(default_auth_header, default_auth_header_value)
+                    .header(header::IF_MATCH, "\"1\"")
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.code, "precondition_failed");
+    }
+}
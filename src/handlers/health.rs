@@ -2,17 +2,153 @@
 #[cfg(test)]
 mod health_test;
 
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
 use axum::Json;
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::AppState;
+
+// how long a single dependency may take before it is reported as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub const STATUS_UP: &str = "up";
+pub const STATUS_DOWN: &str = "down";
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct HealthResponse {
     status: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DependencyStatus {
+    status: String,
+    // a failing critical dependency makes the service not ready
+    critical: bool,
+    latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReadinessResponse {
+    status: String,
+    checks: BTreeMap<String, DependencyStatus>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "The service is up", body = HealthResponse),
+    ),
+)]
 pub async fn get_health() -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "healthy".to_owned(),
     })
 }
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "The process is running", body = HealthResponse),
+    ),
+)]
+pub async fn get_live() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "alive".to_owned(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "All critical dependencies are up", body = ReadinessResponse),
+        (status = 503, description = "A critical dependency is down or the service is shutting down", body = ReadinessResponse),
+    ),
+)]
+pub async fn get_ready(State(state): State<AppState>) -> impl IntoResponse {
+    let conn = state.conn.clone();
+    let authentication = state.authentication.clone();
+
+    let (database, jwks) = tokio::join!(
+        check(true, async move {
+            conn.ping().await.map_err(|err| err.to_string())
+        }),
+        check(true, async move {
+            match authentication.check_keys().await {
+                Ok(0) => Err("no signing keys loaded".to_owned()),
+                Ok(_) => Ok(()),
+                Err(err) => Err(err.to_string()),
+            }
+        }),
+    );
+
+    let checks = BTreeMap::from([("database".to_owned(), database), ("jwks".to_owned(), jwks)]);
+
+    let ready = checks
+        .values()
+        .all(|check| !check.critical || check.status == STATUS_UP);
+
+    let (status_code, status) = match (state.readiness.is_draining(), ready) {
+        (true, _) => (StatusCode::SERVICE_UNAVAILABLE, "draining"),
+        (false, true) => (StatusCode::OK, "ready"),
+        (false, false) => (StatusCode::SERVICE_UNAVAILABLE, "not_ready"),
+    };
+
+    (
+        status_code,
+        Json(ReadinessResponse {
+            status: status.to_owned(),
+            checks,
+        }),
+    )
+}
+
+async fn check(
+    critical: bool,
+    probe: impl Future<Output = Result<(), String>>,
+) -> DependencyStatus {
+    let started = Instant::now();
+
+    let res = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(res) => res,
+        Err(_) => Err(format!("timed out after {:?}", CHECK_TIMEOUT)),
+    };
+
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    match res {
+        Ok(()) => DependencyStatus {
+            status: STATUS_UP.to_owned(),
+            critical,
+            latency_ms,
+            error: None,
+        },
+        Err(err) => {
+            tracing::warn!("readiness check failed: {}", err);
+            DependencyStatus {
+                status: STATUS_DOWN.to_owned(),
+                critical,
+                latency_ms,
+                error: Some(err),
+            }
+        }
+    }
+}
@@ -20,6 +20,7 @@ async fn test_health() {
         conn: Arc::new(conn),
         authentication: Arc::new(authentication::MockIAuthentication::new()),
         authorization: Arc::new(authorization::MockIAuthorization::new()),
+        readiness: Default::default(),
     });
 
     let response = my_router
@@ -33,3 +34,142 @@ async fn test_health() {
     let body: HealthResponse = serde_json::from_slice(&body).unwrap();
     assert_eq!(body.status, "healthy");
 }
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_live() {
+    use crate::authentication;
+    use crate::authorization;
+    use crate::handlers::router;
+    use crate::handlers::{health::HealthResponse, AppState};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use sea_orm::DatabaseConnection;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    // liveness never touches dependencies, even a disconnected database
+    let my_router = router(AppState {
+        conn: Arc::new(DatabaseConnection::Disconnected),
+        authentication: Arc::new(authentication::MockIAuthentication::new()),
+        authorization: Arc::new(authorization::MockIAuthorization::new()),
+        readiness: Default::default(),
+    });
+
+    let response = my_router
+        .oneshot(
+            Request::builder()
+                .uri("/health/live")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: HealthResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body.status, "alive");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_ready() {
+    use crate::authentication::{self, AuthError};
+    use crate::authorization;
+    use crate::handlers::router;
+    use crate::handlers::{health::ReadinessResponse, AppState};
+    use crate::shutdown::Readiness;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    let cases = [
+        (
+            MockDatabase::new(DatabaseBackend::Postgres).into_connection(),
+            Ok(2),
+            StatusCode::OK,
+            "ready",
+            "up",
+            "up",
+            false,
+        ),
+        (
+            MockDatabase::new(DatabaseBackend::Postgres).into_connection(),
+            Err(()),
+            StatusCode::SERVICE_UNAVAILABLE,
+            "not_ready",
+            "up",
+            "down",
+            false,
+        ),
+        (
+            DatabaseConnection::Disconnected,
+            Ok(2),
+            StatusCode::SERVICE_UNAVAILABLE,
+            "not_ready",
+            "down",
+            "up",
+            false,
+        ),
+        // readiness fails as soon as shutdown starts, even with every dependency up
+        (
+            MockDatabase::new(DatabaseBackend::Postgres).into_connection(),
+            Ok(2),
+            StatusCode::SERVICE_UNAVAILABLE,
+            "draining",
+            "up",
+            "up",
+            true,
+        ),
+    ];
+
+    for (conn, keys, status_code, status, database, jwks, draining) in cases {
+        let mut auth = authentication::MockIAuthentication::new();
+        auth.expect_check_keys().times(1).returning(move || {
+            Box::pin(std::future::ready(keys.map_err(|_| {
+                AuthError::RequestFailed(anyhow::anyhow!("unavailable"))
+            })))
+        });
+
+        let readiness = Readiness::default();
+        if draining {
+            readiness.start_draining();
+        }
+
+        let my_router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::new(auth),
+            authorization: Arc::new(authorization::MockIAuthorization::new()),
+            readiness: readiness.clone(),
+        });
+
+        let response = my_router
+            .oneshot(
+                Request::builder()
+                    .uri("/health/ready")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), status_code);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: ReadinessResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.status, status);
+        assert_eq!(body.checks["database"].status, database);
+        assert_eq!(body.checks["jwks"].status, jwks);
+        assert!(body.checks.values().all(|check| check.critical));
+    }
+}
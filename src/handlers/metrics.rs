@@ -0,0 +1,45 @@
+#[path = "metrics_test.rs"]
+#[cfg(test)]
+mod metrics_test;
+
+use std::time::Instant;
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request},
+    http::{header, Response},
+    middleware::Next,
+    response::IntoResponse,
+};
+
+use crate::metrics;
+
+// records every request against the route template it matched, e.g.
+// "/users/{user_id}", rather than the concrete path
+pub async fn middleware(req: Request, next: Next) -> Response<Body> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| metrics::UNMATCHED_ROUTE.to_owned());
+
+    let response = next.run(req).await;
+
+    metrics::get().observe_request(
+        method.as_str(),
+        route.as_str(),
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+
+    response
+}
+
+pub async fn get_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::get().render(),
+    )
+}
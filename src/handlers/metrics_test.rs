@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::{
+        authentication, authorization,
+        handlers::{router, AppState},
+        models, test_utils,
+    };
+
+    async fn get_metrics(router: axum::Router) -> String {
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_metrics_use_route_template() {
+        let router = router(AppState {
+            conn: Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection()),
+            authentication: Arc::new(authentication::MockIAuthentication::new()),
+            authorization: Arc::new(authorization::MockIAuthorization::new()),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/users/{}", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let metrics = get_metrics(router).await;
+        assert!(metrics.contains(
+            r#"http_requests_total{method="GET",route="/users/{user_id}",status="401"}"#
+        ));
+        assert!(metrics.contains(
+            r#"http_request_duration_seconds_count{method="GET",route="/users/{user_id}",status="401"}"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_authorization_denial_metrics() {
+        let user_db = models::user::Model {
+            user_id: Uuid::new_v4(),
+            first_name: None,
+            last_name: None,
+            auth0_id: Some("default_auth0_id".into()),
+            auth0_id_index: None,
+            role: "user".to_owned(),
+            created_at: chrono::Utc::now().into(),
+            updated_at: chrono::Utc::now().into(),
+            deleted_at: None,
+            version: 1,
+        };
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user_db]])
+            .into_connection();
+
+        let (default_auth_header, default_auth_header_value) =
+            test_utils::get_default_auth_header();
+
+        let router = router(AppState {
+            conn: Arc::new(conn),
+            authentication: Arc::from(test_utils::get_default_auth()),
+            authorization: Arc::new(authorization::metered::MeteredAuthorization::new(Arc::new(
+                authorization::Authorization {},
+            ))),
+            readiness: Default::default(),
+        });
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/users")
+                    .header(default_auth_header, default_auth_header_value)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let metrics = get_metrics(router).await;
+        assert!(metrics.contains(r#"authorization_denials_total{check="can_list_users"}"#));
+    }
+}
@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::future;
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+    use utoipa::OpenApi;
+
+    use crate::{
+        authentication, authorization,
+        config::DocsConfig,
+        handlers::{
+            openapi::ApiDoc,
+            router, router_with_config,
+            routes::{api_routes, public_routes},
+            AppState,
+        },
+    };
+
+    // routes that aren't part of the API and so aren't documented
+    const UNDOCUMENTED: [&str; 3] = ["/metrics", "/docs", "/openapi.json"];
+
+    fn docs_config() -> DocsConfig {
+        DocsConfig {
+            ui: true,
+            redoc_integrity: Some("sha384-abc".to_owned()),
+            ..DocsConfig::default()
+        }
+    }
+
+    fn app_state() -> AppState {
+        let mut auth = authentication::MockIAuthentication::new();
+        auth.expect_validate_token().returning(|_| {
+            Box::pin(future::ready(Ok(authentication::Claims {
+                sub: "default_auth0_id".to_owned(),
+            })))
+        });
+
+        AppState {
+            conn: Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection()),
+            authentication: Arc::new(auth),
+            authorization: Arc::new(authorization::MockIAuthorization::new()),
+            readiness: Default::default(),
+        }
+    }
+
+    fn get_router() -> axum::Router {
+        router(app_state())
+    }
+
+    #[tokio::test]
+    async fn test_get_openapi() {
+        let response = get_router()
+            .oneshot(
+                Request::builder()
+                    .uri("/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["openapi"], "3.1.0");
+        assert!(body["paths"]["/users/{user_id}"]["put"].is_object());
+        assert!(body["components"]["schemas"]["Problem"].is_object());
+        assert!(body["components"]["securitySchemes"]["bearer_auth"].is_object());
+    }
+
+    // every documented path must be routed with exactly the documented
+    // methods, which the router reports in the Allow header of a 405
+    #[tokio::test]
+    async fn test_openapi_matches_router() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+
+        assert!(!paths.is_empty());
+
+        for (path, operations) in paths {
+            let documented = operations
+                .as_object()
+                .unwrap()
+                .keys()
+                .filter(|key| key.as_str() != "parameters")
+                .map(|method| method.to_uppercase())
+                .collect::<BTreeSet<String>>();
+
+            let uri = path
+                .split('/')
+                .map(|segment| match segment.starts_with('{') {
+                    true => uuid::Uuid::new_v4().to_string(),
+                    false => segment.to_owned(),
+                })
+                .collect::<Vec<String>>()
+                .join("/");
+
+            let response = get_router()
+                .oneshot(
+                    Request::builder()
+                        .method(Method::TRACE)
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{path} is documented but not routed"
+            );
+
+            let routed = response
+                .headers()
+                .get(header::ALLOW)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .split(',')
+                .map(|method| method.trim().to_owned())
+                .filter(|method| method != "HEAD")
+                .collect::<BTreeSet<String>>();
+
+            assert_eq!(routed, documented, "methods for {path} drifted");
+        }
+    }
+
+    // every routed method and path must be documented, apart from the routes
+    // that aren't part of the API
+    #[test]
+    fn test_router_matches_openapi() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        let routes = public_routes(&docs_config())
+            .into_iter()
+            .chain(api_routes())
+            .map(|(method, path, _)| (method, path))
+            .collect::<Vec<_>>();
+
+        for path in UNDOCUMENTED {
+            assert!(
+                routes.iter().any(|(_, routed)| *routed == path),
+                "{path} is allowed undocumented but not routed"
+            );
+        }
+
+        for (method, path) in routes {
+            if UNDOCUMENTED.contains(&path) {
+                continue;
+            }
+
+            let method = method.as_str().to_lowercase();
+            assert!(
+                spec["paths"][path][&method].is_object(),
+                "{method} {path} is routed but not documented"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_docs() {
+        let response = router_with_config(app_state(), &Default::default(), &docs_config())
+            .oneshot(Request::builder().uri("/docs").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"integrity="sha384-abc" crossorigin="anonymous""#));
+        assert!(body.contains("redoc@2.1.5"));
+    }
+
+    #[tokio::test]
+    async fn test_get_docs_disabled() {
+        let response = get_router()
+            .oneshot(Request::builder().uri("/docs").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::anyhow;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::errors::{self, ServerError};
+use crate::validation::FieldError;
+
+pub const DEFAULT_LIMIT: u64 = 50;
+pub const MAX_LIMIT: u64 = 100;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+// the position of the last item on a page: the value of the sort column and
+// the id used to break ties between rows sharing that value
+#[derive(Serialize, Deserialize)]
+pub struct Cursor {
+    pub value: Option<String>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Cursor, ServerError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|err| errors::ServerError::InvalidCursor(anyhow!(err)))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|err| errors::ServerError::InvalidCursor(anyhow!(err)))
+    }
+}
+
+pub fn parse_limit(limit: Option<u64>) -> Result<u64, ServerError> {
+    match limit {
+        None => Ok(DEFAULT_LIMIT),
+        Some(limit) if (1..=MAX_LIMIT).contains(&limit) => Ok(limit),
+        Some(_) => Err(errors::ServerError::Validation(vec![FieldError::new(
+            "limit",
+            "out_of_range",
+            format!("must be between 1 and {MAX_LIMIT}"),
+        )])),
+    }
+}
@@ -0,0 +1,100 @@
+#[path = "preconditions_test.rs"]
+#[cfg(test)]
+mod preconditions_test;
+
+use std::convert::Infallible;
+
+use axum::extract::FromRequestParts;
+use axum::http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::errors::ServerError;
+
+// the strong ETag of a user or group at `version`
+pub fn etag(version: i32) -> String {
+    format!("\"{version}\"")
+}
+
+// the If-Match and If-None-Match headers of a request, evaluated against the
+// version of the resource it targets
+#[derive(Debug, Default)]
+pub struct Preconditions {
+    if_match: Option<String>,
+    if_none_match: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for Preconditions
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Preconditions::from_headers(&parts.headers))
+    }
+}
+
+impl Preconditions {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        // repeated headers are combined into one list
+        let joined = |name| {
+            let values = headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect::<Vec<&str>>();
+
+            (!values.is_empty()).then(|| values.join(","))
+        };
+
+        Preconditions {
+            if_match: joined(header::IF_MATCH),
+            if_none_match: joined(header::IF_NONE_MATCH),
+        }
+    }
+
+    // fails unless If-Match is absent or lists the current version, comparing
+    // strongly so weak tags never match
+    pub fn check_match(&self, version: i32) -> Result<(), ServerError> {
+        let Some(if_match) = &self.if_match else {
+            return Ok(());
+        };
+
+        let current = etag(version);
+        let matches = tags(if_match).any(|tag| tag == "*" || tag == current);
+
+        match matches {
+            true => Ok(()),
+            false => Err(ServerError::PreconditionFailed),
+        }
+    }
+
+    // whether If-None-Match lists the current version, comparing weakly as
+    // caches may have been handed a weakened tag
+    pub fn not_modified(&self, version: i32) -> bool {
+        let Some(if_none_match) = &self.if_none_match else {
+            return false;
+        };
+
+        let current = etag(version);
+        tags(if_none_match).any(|tag| tag == "*" || tag.trim_start_matches("W/") == current)
+    }
+}
+
+fn tags(header: &str) -> impl Iterator<Item = &str> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+}
+
+// `body` with the ETag of `version`, or an empty 304 when the client already
+// has that version
+pub fn with_etag(preconditions: &Preconditions, version: i32, body: impl IntoResponse) -> Response {
+    let etag = HeaderValue::from_str(&etag(version)).expect("an etag is a valid header value");
+
+    match preconditions.not_modified(version) {
+        true => (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response(),
+        false => ([(header::ETAG, etag)], body).into_response(),
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+
+    use crate::errors::ServerError;
+    use crate::handlers::preconditions::{etag, with_etag, Preconditions};
+
+    fn preconditions(headers: &[(header::HeaderName, &'static str)]) -> Preconditions {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(name, HeaderValue::from_static(value));
+        }
+        Preconditions::from_headers(&map)
+    }
+
+    #[test]
+    fn test_if_match() {
+        assert!(preconditions(&[]).check_match(3).is_ok());
+        assert!(preconditions(&[(header::IF_MATCH, "\"3\"")])
+            .check_match(3)
+            .is_ok());
+        assert!(preconditions(&[(header::IF_MATCH, "*")])
+            .check_match(3)
+            .is_ok());
+        assert!(preconditions(&[(header::IF_MATCH, "\"1\", \"3\"")])
+            .check_match(3)
+            .is_ok());
+
+        // stale, and weak tags never match strongly
+        for if_match in ["\"2\"", "W/\"3\"", "3"] {
+            assert!(matches!(
+                preconditions(&[(header::IF_MATCH, if_match)]).check_match(3),
+                Err(ServerError::PreconditionFailed)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        assert!(!preconditions(&[]).not_modified(3));
+        assert!(preconditions(&[(header::IF_NONE_MATCH, "\"3\"")]).not_modified(3));
+        assert!(preconditions(&[(header::IF_NONE_MATCH, "W/\"3\"")]).not_modified(3));
+        assert!(preconditions(&[(header::IF_NONE_MATCH, "*")]).not_modified(3));
+        assert!(preconditions(&[
+            (header::IF_NONE_MATCH, "\"1\""),
+            (header::IF_NONE_MATCH, "\"3\"")
+        ])
+        .not_modified(3));
+        assert!(!preconditions(&[(header::IF_NONE_MATCH, "\"2\"")]).not_modified(3));
+    }
+
+    #[test]
+    fn test_with_etag() {
+        let response = with_etag(&preconditions(&[]), 3, "body");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], etag(3));
+
+        let response = with_etag(
+            &preconditions(&[(header::IF_NONE_MATCH, "\"3\"")]),
+            3,
+            "body",
+        );
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag(3));
+    }
+}
@@ -0,0 +1,38 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Response},
+    middleware::Next,
+};
+
+use crate::errors::Problem;
+use crate::telemetry::REQUEST_ID_HEADER;
+
+// completes problem details bodies with the request path and request id
+pub async fn middleware(req: Request, next: Next) -> Response<Body> {
+    let instance = req.uri().path().to_owned();
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|request_id| request_id.to_str().ok())
+        .map(|request_id| request_id.to_owned());
+
+    let response = next.run(req).await;
+
+    let Some(problem) = response.extensions().get::<Problem>() else {
+        return response;
+    };
+
+    let problem = Problem {
+        instance: Some(instance),
+        request_id,
+        ..problem.clone()
+    };
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = serde_json::to_vec(&problem).unwrap_or_default();
+    parts.extensions.insert(problem);
+
+    Response::from_parts(parts, Body::from(body))
+}
@@ -1,19 +1,36 @@
+#[path = "routes_test.rs"]
+#[cfg(test)]
+mod routes_test;
+
+use std::future;
 use std::sync::Arc;
 
-use axum::routing::delete;
-use axum::routing::get;
-use axum::routing::post;
-use axum::routing::put;
+use axum::body::Body;
+use axum::handler::Handler;
+use axum::http::{Method, Request};
+use axum::routing::{on, MethodFilter, MethodRouter};
 use axum::{middleware, Router};
 use sea_orm::DatabaseConnection;
 
 use crate::authentication;
 use crate::authorization;
+use crate::config::{CorsConfig, DocsConfig};
+use crate::errors::ServerError;
+use crate::shutdown::Readiness;
+use crate::{logging, telemetry};
 use tower::ServiceBuilder;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
 
 use super::authentication as authentication_middleware;
 
+use super::cors;
+use super::group_users;
+use super::groups;
 use super::health;
+use super::metrics;
+use super::openapi;
+use super::problem_details;
 
 use super::users;
 
@@ -22,20 +39,150 @@ pub struct AppState {
     pub conn: Arc<DatabaseConnection>,
     pub authentication: Arc<dyn authentication::IAuthentication>,
     pub authorization: Arc<dyn authorization::IAuthorization>,
+    pub readiness: Readiness,
 }
 
+// the router with no cross-origin access allowed and no docs UI, for tests
+// that exercise neither
+#[cfg(test)]
 pub fn router(app_state: AppState) -> Router {
-    Router::new().route("/", get(health::get_health)).merge(
-        Router::new()
-            .route("/users", get(users::list_users))
-            .route("/users/{user_id}", get(users::get_user))
-            .route("/users", post(users::create_user))
-            .route("/users/{user_id}", put(users::modify_user))
-            .route("/users/{user_id}", delete(users::delete_user))
-            .layer(ServiceBuilder::new().layer(middleware::from_fn_with_state(
-                app_state.clone(),
-                authentication_middleware::middleware,
-            )))
-            .with_state(app_state),
-    )
+    router_with_config(app_state, &CorsConfig::default(), &DocsConfig::default())
+}
+
+// a route as the method and path it is registered under, kept alongside the
+// handler so tests can list every route the router serves
+pub(super) type Route = (Method, &'static str, MethodRouter<AppState>);
+
+fn route<H, T>(method: Method, path: &'static str, handler: H) -> Route
+where
+    H: Handler<T, AppState>,
+    T: 'static,
+{
+    let filter = MethodFilter::try_from(method.clone()).expect("a routable method");
+    (method, path, on(filter, handler))
+}
+
+// served without authentication
+pub(super) fn public_routes(docs: &DocsConfig) -> Vec<Route> {
+    let mut routes = vec![
+        route(Method::GET, "/", health::get_health),
+        route(Method::GET, "/health/live", health::get_live),
+        route(Method::GET, "/health/ready", health::get_ready),
+        route(Method::GET, "/metrics", metrics::get_metrics),
+        route(Method::GET, "/openapi.json", openapi::get_openapi),
+    ];
+
+    if let Some(page) = openapi::docs_page(docs) {
+        routes.push(route(Method::GET, "/docs", move || {
+            future::ready(page.clone())
+        }));
+    }
+
+    routes
+}
+
+pub(super) fn api_routes() -> Vec<Route> {
+    vec![
+        route(Method::GET, "/users", users::list_users),
+        route(Method::GET, "/users/{user_id}", users::get_user),
+        route(Method::POST, "/users", users::create_user),
+        route(Method::PUT, "/users/{user_id}", users::modify_user),
+        route(Method::PATCH, "/users/{user_id}", users::patch_user),
+        route(Method::DELETE, "/users/{user_id}", users::delete_user),
+        route(
+            Method::POST,
+            "/users/{user_id}/restore",
+            users::restore_user,
+        ),
+        route(Method::GET, "/groups", groups::list_groups),
+        route(Method::GET, "/groups/{group_id}", groups::get_group),
+        route(Method::POST, "/groups", groups::create_group),
+        route(Method::PUT, "/groups/{group_id}", groups::modify_group),
+        route(Method::DELETE, "/groups/{group_id}", groups::delete_group),
+        route(
+            Method::GET,
+            "/groups/{group_id}/users",
+            group_users::list_group_users,
+        ),
+        route(
+            Method::POST,
+            "/groups/{group_id}/users",
+            group_users::create_group_user,
+        ),
+        route(
+            Method::PUT,
+            "/groups/{group_id}/users/{user_id}",
+            group_users::modify_group_user,
+        ),
+        route(
+            Method::DELETE,
+            "/groups/{group_id}/users/{user_id}",
+            group_users::delete_group_user,
+        ),
+        route(
+            Method::GET,
+            "/users/{user_id}/groups",
+            group_users::list_user_groups,
+        ),
+    ]
+}
+
+// unknown paths and methods are answered with a problem like any other error,
+// a 405 keeps the Allow header the method router adds
+async fn not_found() -> ServerError {
+    ServerError::NotFound
+}
+
+async fn method_not_allowed() -> ServerError {
+    ServerError::MethodNotAllowed
+}
+
+fn add_routes(router: Router<AppState>, routes: Vec<Route>) -> Router<AppState> {
+    routes
+        .into_iter()
+        .fold(router, |router, (_, path, method_router)| {
+            router.route(path, method_router)
+        })
+}
+
+pub fn router_with_config(
+    app_state: AppState,
+    cors_config: &CorsConfig,
+    docs_config: &DocsConfig,
+) -> Router {
+    add_routes(Router::new(), public_routes(docs_config))
+        .merge(
+            // a route layer so unknown paths and methods reach the fallbacks
+            // without credentials
+            add_routes(Router::new(), api_routes()).route_layer(ServiceBuilder::new().layer(
+                middleware::from_fn_with_state(
+                    app_state.clone(),
+                    authentication_middleware::middleware,
+                ),
+            )),
+        )
+        // after every route is added, the 405 fallback is set per route
+        .method_not_allowed_fallback(method_not_allowed)
+        .fallback(not_found)
+        .with_state(app_state)
+        .layer(
+            ServiceBuilder::new()
+                .layer(middleware::from_fn(metrics::middleware))
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(PropagateRequestIdLayer::x_request_id())
+                // after the request id is set so it is recorded on the request span
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(telemetry::make_request_span)
+                        .on_request(|req: &Request<Body>, _: &tracing::Span| {
+                            tracing::debug!(
+                                headers = ?logging::redacted_headers(req.headers()),
+                                "started processing request"
+                            )
+                        }),
+                )
+                .layer(middleware::from_fn(problem_details::middleware))
+                // outside the routes so preflight requests never reach authentication
+                .layer(cors::layer(cors_config)),
+        )
 }
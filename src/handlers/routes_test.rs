@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tower::ServiceExt;
+
+    use crate::{
+        authentication, authorization,
+        errors::{Problem, PROBLEM_CONTENT_TYPE},
+        handlers::{router, AppState},
+    };
+
+    // authentication and authorization mocks have no expectations, so any
+    // request reaching them fails the test
+    fn get_router() -> axum::Router {
+        router(AppState {
+            conn: Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection()),
+            authentication: Arc::new(authentication::MockIAuthentication::new()),
+            authorization: Arc::new(authorization::MockIAuthorization::new()),
+            readiness: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_unknown_path() {
+        let response = get_router()
+            .oneshot(
+                Request::builder()
+                    .uri("/unknown")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_CONTENT_TYPE
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.status, 404);
+        assert_eq!(body.code, "not_found");
+        assert_eq!(body.instance.as_deref(), Some("/unknown"));
+        assert!(body.request_id.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_method_not_allowed() {
+        let response = get_router()
+            .oneshot(
+                Request::builder()
+                    .method(Method::PATCH)
+                    .uri("/groups")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_CONTENT_TYPE
+        );
+        assert_eq!(
+            response.headers().get(header::ALLOW).unwrap(),
+            "GET,HEAD,POST"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Problem = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.status, 405);
+        assert_eq!(body.code, "method_not_allowed");
+        assert_eq!(body.instance.as_deref(), Some("/groups"));
+        assert!(body.request_id.is_some());
+    }
+}
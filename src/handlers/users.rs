@@ -3,25 +3,40 @@
 mod users_test;
 
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::IntoResponse;
-use axum::{Extension, Json};
+use axum::Extension;
 
 use sea_orm::entity::*;
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
 use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
+use sea_orm::{Condition, DbErr, Order, QueryOrder, QuerySelect, SqlErr, TransactionTrait};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::authentication::Claims;
-use crate::errors::{self, ServerError};
+use crate::encryption::{self, Encrypted};
+use crate::errors::{self, Problem, ServerError};
+use crate::models::group_user::Entity as GroupUser;
 use crate::models::user::Entity as User;
+use crate::validation::{self, FieldError, Validate, Validator};
 use crate::{authorization, models};
 use anyhow::anyhow;
 
+use super::authorization::{
+    fetch_actor, fetch_user_by_auth_id, fetch_user_by_user_id, parse_user_id,
+};
+use super::extractors::{present, Json, MergePatch, Query};
+use super::group_users::ensure_other_owner;
+use super::pagination::{parse_limit, Cursor, Page, MAX_LIMIT};
+use super::preconditions::{etag, with_etag, Preconditions};
 use super::AppState;
 
-#[derive(Serialize, Deserialize)]
+const SORT_FIELDS: [&str; 4] = ["created_at", "updated_at", "first_name", "last_name"];
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     user_id: Uuid,
     first_name: Option<String>,
@@ -30,223 +45,763 @@ pub struct UserResponse {
     updated_at: chrono::DateTime<chrono::FixedOffset>,
 }
 
-#[derive(Serialize, Deserialize)]
+impl From<&models::user::Model> for UserResponse {
+    fn from(user: &models::user::Model) -> Self {
+        UserResponse {
+            user_id: user.user_id.to_owned(),
+            first_name: user.first_name.to_owned().map(String::from),
+            last_name: user.last_name.to_owned().map(String::from),
+            created_at: user.created_at.to_owned(),
+            updated_at: user.updated_at.to_owned(),
+        }
+    }
+}
+
+// the fields users can be modified through, a PUT replaces all of them
+const MODIFIABLE_FIELDS: [&str; 2] = ["first_name", "last_name"];
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ModifyUser {
     first_name: Option<String>,
     last_name: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+// a merge patch, where a field left out is kept and a null field is cleared
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PatchUser {
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    first_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    last_name: Option<Option<String>>,
+}
+
+impl PatchUser {
+    // the fields the patch changes, with the value they're changed to
+    fn fields(&self) -> Vec<(&'static str, Option<&str>)> {
+        [
+            ("first_name", &self.first_name),
+            ("last_name", &self.last_name),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| value.as_ref().map(|value| (field, value.as_deref())))
+        .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    limit: Option<u64>,
+    cursor: Option<String>,
+    sort: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    role: Option<String>,
+    created_after: Option<chrono::DateTime<chrono::FixedOffset>>,
+    created_before: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct CreateUser {
     auth0_id: String,
     first_name: Option<String>,
     last_name: Option<String>,
 }
 
+impl Validate for ModifyUser {
+    fn validate(&self) -> Result<(), ServerError> {
+        Validator::new()
+            .optional_text(
+                "first_name",
+                self.first_name.as_deref(),
+                validation::MAX_NAME_LENGTH,
+            )
+            .optional_text(
+                "last_name",
+                self.last_name.as_deref(),
+                validation::MAX_NAME_LENGTH,
+            )
+            .finish()
+    }
+}
+
+impl Validate for PatchUser {
+    fn validate(&self) -> Result<(), ServerError> {
+        let mut validator = Validator::new();
+
+        for (field, value) in self.fields() {
+            validator.optional_text(field, value, validation::MAX_NAME_LENGTH);
+        }
+
+        validator.finish()
+    }
+}
+
+impl Validate for CreateUser {
+    fn validate(&self) -> Result<(), ServerError> {
+        Validator::new()
+            .auth0_id("auth0_id", self.auth0_id.as_str())
+            .optional_text(
+                "first_name",
+                self.first_name.as_deref(),
+                validation::MAX_NAME_LENGTH,
+            )
+            .optional_text(
+                "last_name",
+                self.last_name.as_deref(),
+                validation::MAX_NAME_LENGTH,
+            )
+            .finish()
+    }
+}
+
+impl Validate for ListUsersQuery {
+    fn validate(&self) -> Result<(), ServerError> {
+        let mut validator = Validator::new();
+
+        if let Some(limit) = self.limit {
+            validator.range("limit", limit, 1, MAX_LIMIT);
+        }
+
+        match parse_sort(self.sort.as_deref()) {
+            Err(_) => {
+                validator.add(FieldError::new(
+                    "sort",
+                    "invalid_choice",
+                    format!(
+                        "must be one of: {}, optionally prefixed with \"-\"",
+                        SORT_FIELDS.join(", ")
+                    ),
+                ));
+            }
+            // the database only sees ciphertext, which can't be ordered
+            Ok((models::user::Column::FirstName | models::user::Column::LastName, _))
+                if encryption::is_enabled() =>
+            {
+                validator.add(encrypted_field_error("sort"));
+            }
+            Ok(_) => {}
+        }
+
+        // nor searched
+        if encryption::is_enabled() {
+            if self.first_name.is_some() {
+                validator.add(encrypted_field_error("first_name"));
+            }
+            if self.last_name.is_some() {
+                validator.add(encrypted_field_error("last_name"));
+            }
+        }
+
+        validator
+            .optional_text(
+                "first_name",
+                self.first_name.as_deref(),
+                validation::MAX_NAME_LENGTH,
+            )
+            .optional_text(
+                "last_name",
+                self.last_name.as_deref(),
+                validation::MAX_NAME_LENGTH,
+            )
+            .finish()
+    }
+}
+
+fn encrypted_field_error(field: &str) -> FieldError {
+    FieldError::new(
+        field,
+        "unsupported",
+        "isn't available while names are encrypted",
+    )
+}
+
+// parses a sort parameter such as "created_at" or "-last_name"
+fn parse_sort(sort: Option<&str>) -> Result<(models::user::Column, Order), ServerError> {
+    let sort = sort.unwrap_or("created_at");
+
+    let (field, order) = match sort.strip_prefix('-') {
+        Some(field) => (field, Order::Desc),
+        None => (sort, Order::Asc),
+    };
+
+    let column = match field {
+        "created_at" => models::user::Column::CreatedAt,
+        "updated_at" => models::user::Column::UpdatedAt,
+        "first_name" => models::user::Column::FirstName,
+        "last_name" => models::user::Column::LastName,
+        _ => {
+            return Err(errors::ServerError::Validation(vec![FieldError::new(
+                "sort",
+                "invalid_choice",
+                format!("must be one of: {}", SORT_FIELDS.join(", ")),
+            )]))
+        }
+    };
+
+    Ok((column, order))
+}
+
+// nullable text columns sort as empty strings so the cursor comparison stays total
+fn sort_expr(column: models::user::Column) -> SimpleExpr {
+    match column {
+        models::user::Column::FirstName | models::user::Column::LastName => {
+            Func::coalesce([Expr::col(column).into(), Expr::val("").into()]).into()
+        }
+        _ => Expr::col(column).into(),
+    }
+}
+
+fn sort_value(user: &models::user::Model, column: models::user::Column) -> Option<String> {
+    match column {
+        models::user::Column::CreatedAt => Some(user.created_at.to_rfc3339()),
+        models::user::Column::UpdatedAt => Some(user.updated_at.to_rfc3339()),
+        models::user::Column::FirstName => user.first_name.to_owned().map(String::from),
+        models::user::Column::LastName => user.last_name.to_owned().map(String::from),
+        _ => None,
+    }
+}
+
+fn cursor_value(cursor: &Cursor, column: models::user::Column) -> Result<Value, ServerError> {
+    match column {
+        models::user::Column::CreatedAt | models::user::Column::UpdatedAt => {
+            let value = cursor.value.as_deref().unwrap_or_default();
+            let value = chrono::DateTime::parse_from_rfc3339(value)
+                .map_err(|err| errors::ServerError::InvalidCursor(anyhow!(err)))?;
+            Ok(value.into())
+        }
+        _ => Ok(cursor.value.to_owned().unwrap_or_default().into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    params(ListUsersQuery),
+    responses(
+        (status = 200, description = "A page of users", body = Page<UserResponse>),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
 pub async fn list_users(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
+    Query(query): Query<ListUsersQuery>,
 ) -> Result<impl IntoResponse, ServerError> {
     let conn = &*state.conn.clone();
     let authorization = state.authorization.clone();
 
-    let users: Vec<models::user::Model> = User::find()
-        .all(conn)
-        .await
-        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
-
-    let user = User::find()
-        .filter(models::user::Column::Auth0Id.eq(claims.sub.to_owned()))
-        .one(conn)
-        .await
-        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?
-        .ok_or(errors::ServerError::Unauthorized)?;
+    let actor = fetch_actor(conn, &claims).await?;
 
     authorization
-        .can_get_user(
-            authorization::User {
-                user_id: user.user_id.to_owned(),
-                role: user.role.to_owned(),
-            },
-            user.user_id.to_owned(),
-        )
+        .can_list_users(actor)
         .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
 
-    Ok(Json(
-        users
+    let limit = parse_limit(query.limit)?;
+    let (column, order) = parse_sort(query.sort.as_deref())?;
+
+    let mut select = models::user::find_active();
+
+    if let Some(first_name) = &query.first_name {
+        select = select.filter(models::user::Column::FirstName.contains(first_name));
+    }
+
+    if let Some(last_name) = &query.last_name {
+        select = select.filter(models::user::Column::LastName.contains(last_name));
+    }
+
+    if let Some(role) = &query.role {
+        select = select.filter(models::user::Column::Role.eq(role.to_owned()));
+    }
+
+    if let Some(created_after) = query.created_after {
+        select = select.filter(models::user::Column::CreatedAt.gte(created_after));
+    }
+
+    if let Some(created_before) = query.created_before {
+        select = select.filter(models::user::Column::CreatedAt.lt(created_before));
+    }
+
+    if let Some(cursor) = &query.cursor {
+        let cursor = Cursor::decode(cursor)?;
+        let value = cursor_value(&cursor, column)?;
+        let key = Expr::expr(sort_expr(column));
+
+        let after = match order {
+            Order::Desc => Condition::any().add(key.clone().lt(value.clone())).add(
+                Condition::all()
+                    .add(key.eq(value))
+                    .add(models::user::Column::UserId.lt(cursor.id)),
+            ),
+            _ => Condition::any().add(key.clone().gt(value.clone())).add(
+                Condition::all()
+                    .add(key.eq(value))
+                    .add(models::user::Column::UserId.gt(cursor.id)),
+            ),
+        };
+
+        select = select.filter(after);
+    }
+
+    let mut users: Vec<models::user::Model> = select
+        .order_by(sort_expr(column), order.clone())
+        .order_by(models::user::Column::UserId, order)
+        .limit(limit + 1)
+        .all(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    // one extra row is fetched to tell whether there is another page
+    let next_cursor = if users.len() as u64 > limit {
+        users.truncate(limit as usize);
+        users.last().map(|user| {
+            Cursor {
+                value: sort_value(user, column),
+                id: user.user_id.to_owned(),
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(Page {
+        data: users
             .iter()
-            .map(|user| UserResponse {
-                user_id: user.user_id.to_owned(),
-                first_name: user.first_name.to_owned(),
-                last_name: user.last_name.to_owned(),
-                created_at: user.created_at.to_owned(),
-                updated_at: user.updated_at.to_owned(),
-            })
+            .map(UserResponse::from)
             .collect::<Vec<UserResponse>>(),
-    ))
+        next_cursor,
+    }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}",
+    tag = "users",
+    params(
+        ("user_id" = String, Path, description = "User id, or \"me\" for the authenticated user"),
+        ("If-None-Match" = Option<String>, Header, description = "ETags the client has, answered with 304 when one is current"),
+    ),
+    responses(
+        (status = 200, description = "The user", body = UserResponse, headers(("ETag" = String, description = "The current version of the user"))),
+        (status = 304, description = "The user hasn't changed since the given ETag"),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
 pub async fn get_user(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
     Extension(claims): Extension<Claims>,
+    preconditions: Preconditions,
 ) -> Result<impl IntoResponse, ServerError> {
     let conn = &*state.conn.clone();
     let authorization = state.authorization.clone();
 
-    let user: models::user::Model = (|| -> Result<_, ServerError> {
-        if user_id == "me" {
-            return Ok(User::find()
-                .filter(models::user::Column::Auth0Id.eq(claims.sub.to_owned()))
-                .one(conn));
-        }
-        let user_id_uuid = uuid::Uuid::parse_str(user_id.as_str())
-            .map_err(|err| errors::ServerError::InvalidUUID(anyhow!(err)))?;
-        Ok(User::find_by_id(user_id_uuid).one(conn))
-    })()?
-    .await
-    .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?
-    .ok_or(errors::ServerError::NotFound)?;
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = parse_user_id(user_id.as_str(), &actor)?;
 
     authorization
-        .can_get_user(
-            authorization::User {
-                user_id: user.user_id.to_owned(),
-                role: user.role.to_owned(),
-            },
-            user.user_id.to_owned(),
-        )
+        .can_get_user(actor, user_id.to_owned())
         .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
 
-    Ok(Json(UserResponse {
-        user_id: user.user_id.to_owned(),
-        first_name: user.first_name.to_owned(),
-        last_name: user.last_name.to_owned(),
-        created_at: user.created_at.to_owned(),
-        updated_at: user.updated_at.to_owned(),
-    }))
+    let user = fetch_user_by_user_id(conn, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    Ok(with_etag(
+        &preconditions,
+        user.version,
+        Json(UserResponse::from(&user)),
+    ))
 }
 
+#[utoipa::path(
+    post,
+    path = "/users",
+    tag = "users",
+    request_body = CreateUser,
+    responses(
+        (status = 200, description = "A user with this auth0_id already exists", body = UserResponse, headers(("ETag" = String, description = "The current version of the user"))),
+        (status = 201, description = "The created user", body = UserResponse, headers(("ETag" = String, description = "The version of the user"))),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
 pub async fn create_user(
     State(state): State<AppState>,
     Json(body): Json<CreateUser>,
 ) -> Result<impl IntoResponse, ServerError> {
     let conn = &*state.conn.clone();
 
-    let user_found_res = User::find()
-        .filter(models::user::Column::Auth0Id.eq(body.auth0_id.to_owned()))
-        .one(conn)
-        .await
-        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+    let user_found_res = fetch_user_by_auth_id(conn, body.auth0_id.as_str()).await?;
 
     if let Some(user_found) = user_found_res {
         return Ok((
             StatusCode::OK,
-            Json(UserResponse {
-                user_id: user_found.user_id.to_owned(),
-                first_name: user_found.first_name.to_owned(),
-                last_name: user_found.last_name.to_owned(),
-                created_at: user_found.created_at.to_owned(),
-                updated_at: user_found.updated_at.to_owned(),
-            }),
+            [(header::ETAG, etag(user_found.version))],
+            Json(UserResponse::from(&user_found)),
         ));
     }
 
     let mut first_name = NotSet;
 
     if body.first_name.is_some() {
-        first_name = Set(body.first_name.to_owned());
+        first_name = Set(body.first_name.to_owned().map(Encrypted::from));
     }
 
     let mut last_name = NotSet;
 
     if body.last_name.is_some() {
-        last_name = Set(body.last_name.to_owned());
+        last_name = Set(body.last_name.to_owned().map(Encrypted::from));
     }
 
-    let user: models::user::Model = models::user::ActiveModel {
+    let res = models::user::ActiveModel {
         user_id: NotSet,
-        auth0_id: Set(Some(body.auth0_id.to_owned())),
+        auth0_id: Set(Some(Encrypted::from(body.auth0_id.as_str()))),
+        auth0_id_index: NotSet,
         role: Set("user".to_owned()),
         first_name,
         last_name,
         created_at: NotSet,
         updated_at: NotSet,
+        deleted_at: NotSet,
+        version: NotSet,
     }
     .insert(conn)
-    .await
-    .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+    .await;
+
+    let user: models::user::Model = match res {
+        Ok(user) => user,
+        // a concurrent request, e.g. the same first login from another tab,
+        // created the user since it was looked up
+        Err(err) if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            let user_found = fetch_user_by_auth_id(conn, body.auth0_id.as_str())
+                .await?
+                .ok_or(errors::ServerError::Conflict)?;
+
+            return Ok((
+                StatusCode::OK,
+                [(header::ETAG, etag(user_found.version))],
+                Json(UserResponse::from(&user_found)),
+            ));
+        }
+        Err(err) => return Err(errors::ServerError::Internal(anyhow!(err))),
+    };
 
     Ok((
         StatusCode::CREATED,
-        Json(UserResponse {
-            user_id: user.user_id.to_owned(),
-            first_name: user.first_name.to_owned(),
-            last_name: user.last_name.to_owned(),
-            created_at: user.created_at.to_owned(),
-            updated_at: user.updated_at.to_owned(),
-        }),
+        [(header::ETAG, etag(user.version))],
+        Json(UserResponse::from(&user)),
     ))
 }
 
+#[utoipa::path(
+    put,
+    path = "/users/{user_id}",
+    tag = "users",
+    params(
+        ("user_id" = String, Path, description = "User id, or \"me\" for the authenticated user"),
+        ("If-Match" = Option<String>, Header, description = "Only update the user if its ETag is one of these"),
+    ),
+    request_body = ModifyUser,
+    responses(
+        (status = 200, description = "The updated user", body = UserResponse, headers(("ETag" = String, description = "The new version of the user"))),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The user has changed since the given ETag", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body isn't application/json; merge patches go to PATCH", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
 pub async fn modify_user(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
     Extension(claims): Extension<Claims>,
+    preconditions: Preconditions,
     Json(body): Json<ModifyUser>,
 ) -> Result<impl IntoResponse, ServerError> {
     let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
 
-    let mut user: models::user::ActiveModel = (|| -> Result<_, ServerError> {
-        if user_id == "me" {
-            return Ok(User::find()
-                .filter(models::user::Column::Auth0Id.eq(claims.sub.to_owned()))
-                .one(conn));
-        }
-        let user_id_uuid = uuid::Uuid::parse_str(user_id.as_str())
-            .map_err(|err| errors::ServerError::InvalidUUID(anyhow!(err)))?;
-        Ok(User::find_by_id(user_id_uuid).one(conn))
-    })()?
-    .await
-    .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?
-    .ok_or(errors::ServerError::NotFound)?
-    .into();
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = parse_user_id(user_id.as_str(), &actor)?;
 
-    if body.first_name.is_some() {
-        user.first_name = Set(body.first_name.to_owned());
+    authorization
+        .can_modify_user(actor.clone(), user_id.to_owned())
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let user = fetch_user_by_user_id(conn, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    preconditions.check_match(user.version)?;
+    let version = user.version;
+
+    let resource = authorization::User {
+        user_id: user.user_id.to_owned(),
+        role: user.role.to_owned(),
+    };
+
+    // every field is replaced, fields left out are cleared
+    for field in MODIFIABLE_FIELDS {
+        authorization
+            .can_modify_user_field(actor.clone(), resource.clone(), field.to_owned())
+            .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
     }
 
-    if body.last_name.is_some() {
-        user.last_name = Set(body.last_name.to_owned());
+    let mut user: models::user::ActiveModel = user.into();
+
+    user.first_name = Set(body.first_name.to_owned().map(Encrypted::from));
+    user.last_name = Set(body.last_name.to_owned().map(Encrypted::from));
+
+    let user_updated = update_user(conn, user, version).await?;
+
+    Ok((
+        [(header::ETAG, etag(user_updated.version))],
+        Json(UserResponse::from(&user_updated)),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/users/{user_id}",
+    tag = "users",
+    params(
+        ("user_id" = String, Path, description = "User id, or \"me\" for the authenticated user"),
+        ("If-Match" = Option<String>, Header, description = "Only update the user if its ETag is one of these"),
+    ),
+    request_body(content = PatchUser, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "The updated user", body = UserResponse, headers(("ETag" = String, description = "The new version of the user"))),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The user has changed since the given ETag", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "The body isn't application/merge-patch+json", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn patch_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    preconditions: Preconditions,
+    MergePatch(body): MergePatch<PatchUser>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = parse_user_id(user_id.as_str(), &actor)?;
+
+    authorization
+        .can_modify_user(actor.clone(), user_id.to_owned())
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let user = fetch_user_by_user_id(conn, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    preconditions.check_match(user.version)?;
+    let version = user.version;
+
+    let resource = authorization::User {
+        user_id: user.user_id.to_owned(),
+        role: user.role.to_owned(),
+    };
+
+    // only the fields in the patch are checked and written, null clears them
+    for (field, _) in body.fields() {
+        authorization
+            .can_modify_user_field(actor.clone(), resource.clone(), field.to_owned())
+            .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
     }
 
-    let user_updated: models::user::Model = user
-        .update(conn)
+    let mut user: models::user::ActiveModel = user.into();
+
+    if let Some(first_name) = body.first_name {
+        user.first_name = Set(first_name.map(Encrypted::from));
+    }
+
+    if let Some(last_name) = body.last_name {
+        user.last_name = Set(last_name.map(Encrypted::from));
+    }
+
+    let user_updated = update_user(conn, user, version).await?;
+
+    Ok((
+        [(header::ETAG, etag(user_updated.version))],
+        Json(UserResponse::from(&user_updated)),
+    ))
+}
+
+// saves a modified user, guarded on the version that was read so a
+// concurrent update isn't silently overwritten
+async fn update_user(
+    conn: &sea_orm::DatabaseConnection,
+    user: models::user::ActiveModel,
+    version: i32,
+) -> Result<models::user::Model, ServerError> {
+    // Entity::update skips the model's hooks, so stamp, bump and index here
+    let user = user
+        .before_save(conn, false)
         .await
         .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
 
-    Ok(Json(UserResponse {
-        user_id: user_updated.user_id.to_owned(),
-        first_name: user_updated.first_name.to_owned(),
-        last_name: user_updated.last_name.to_owned(),
-        created_at: user_updated.created_at.to_owned(),
-        updated_at: user_updated.updated_at.to_owned(),
-    }))
+    User::update(user)
+        .filter(models::user::Column::Version.eq(version))
+        .exec(conn)
+        .await
+        .map_err(|err| match err {
+            DbErr::RecordNotUpdated => errors::ServerError::PreconditionFailed,
+            _ => errors::ServerError::Internal(anyhow!(err)),
+        })
 }
 
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}",
+    tag = "users",
+    params(
+        ("user_id" = String, Path, description = "User id, or \"me\" for the authenticated user"),
+        ("If-Match" = Option<String>, Header, description = "Only delete the user if its ETag is one of these"),
+    ),
+    responses(
+        (status = 204, description = "The user was deleted"),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Resource not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The user is the last owner of a group", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The user has changed since the given ETag", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
 pub async fn delete_user(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    preconditions: Preconditions,
 ) -> Result<impl IntoResponse, ServerError> {
     let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
 
-    let user_id_uuid = uuid::Uuid::parse_str(user_id.as_str())
-        .map_err(|err| errors::ServerError::InvalidUUID(anyhow!(err)))?;
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = parse_user_id(user_id.as_str(), &actor)?;
 
-    User::delete_by_id(user_id_uuid)
-        .exec(conn)
+    authorization
+        .can_delete_user(actor, user_id.to_owned())
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let user = fetch_user_by_user_id(conn, user_id)
+        .await?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    preconditions.check_match(user.version)?;
+
+    let txn = conn
+        .begin()
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    // a deleted owner no longer counts, so the groups the user owns need
+    // another owner first
+    let owned: Vec<models::group_user::Model> = GroupUser::find()
+        .filter(models::group_user::Column::UserId.eq(user_id))
+        .filter(models::group_user::Column::Role.eq(authorization::GROUP_ROLE_OWNER))
+        .all(&txn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    for membership in owned {
+        ensure_other_owner(&txn, membership.group_id, user_id).await?;
+    }
+
+    // the row and its memberships are kept until the retention period ends,
+    // so the user can be restored
+    let res = User::update_many()
+        .col_expr(
+            models::user::Column::DeletedAt,
+            Expr::current_timestamp().into(),
+        )
+        .col_expr(
+            models::user::Column::Version,
+            Expr::col(models::user::Column::Version).add(1),
+        )
+        .filter(models::user::Column::UserId.eq(user_id))
+        .filter(models::user::Column::Version.eq(user.version))
+        .filter(models::user::Column::DeletedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
+
+    // deleted or updated since it was fetched
+    if res.rows_affected == 0 {
+        return Err(errors::ServerError::PreconditionFailed);
+    }
+
+    txn.commit()
         .await
         .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/restore",
+    tag = "users",
+    params(("user_id" = String, Path, description = "Id of the deleted user")),
+    responses(
+        (status = 200, description = "The restored user", body = UserResponse, headers(("ETag" = String, description = "The new version of the user"))),
+        (status = 401, description = "Missing or invalid bearer token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "The actor is not allowed to perform this action", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No deleted user with this id", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Another user has signed up with this user's auth0_id since", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid path, query or body parameters", body = Problem, content_type = "application/problem+json"),
+    ),
+)]
+pub async fn restore_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ServerError> {
+    let conn = &*state.conn.clone();
+    let authorization = state.authorization.clone();
+
+    let actor = fetch_actor(conn, &claims).await?;
+    let user_id = validation::parse_uuid("user_id", user_id.as_str())?;
+
+    authorization
+        .can_restore_user(actor, user_id.to_owned())
+        .map_err(|err| errors::ServerError::UnauthorizedReason(anyhow!(err)))?;
+
+    let user = User::find_by_id(user_id)
+        .filter(models::user::Column::DeletedAt.is_not_null())
+        .one(conn)
+        .await
+        .map_err(|err| errors::ServerError::Internal(anyhow!(err)))?
+        .ok_or(errors::ServerError::NotFound)?;
+
+    let mut user: models::user::ActiveModel = user.into();
+    user.deleted_at = Set(None);
+
+    let user_restored: models::user::Model =
+        user.update(conn).await.map_err(|err| match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => errors::ServerError::Conflict,
+            _ => errors::ServerError::Internal(anyhow!(err)),
+        })?;
+
+    Ok((
+        [(header::ETAG, etag(user_restored.version))],
+        Json(UserResponse::from(&user_restored)),
+    ))
+}
@@ -0,0 +1,154 @@
+#[path = "key_rotation_test.rs"]
+#[cfg(test)]
+mod key_rotation_test;
+
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::encryption::{self, EncryptionError, Keyring};
+use crate::models::user;
+
+// read by the set_updated_at and bump_version triggers, which leave
+// updated_at and version alone for the rest of the transaction when it is on
+pub const SKIP_TOUCH: &str = "SET LOCAL app.skip_touch = 'on'";
+
+#[derive(Debug, Error)]
+pub enum RotationError {
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    #[error("user {0}: {1}")]
+    Decrypt(Uuid, EncryptionError),
+}
+
+// the encrypted columns as stored, without decrypting them
+#[derive(Debug, FromQueryResult)]
+pub struct StoredUser {
+    pub user_id: Uuid,
+    pub auth0_id: Option<String>,
+    pub auth0_id_index: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Rotation {
+    pub scanned: usize,
+    pub rotated: usize,
+}
+
+// re-encrypts every user whose fields aren't encrypted with the current key,
+// including plaintext rows written before encryption was enabled, one locked
+// batch per transaction so the app keeps serving while it runs; the rows
+// keep their updated_at and version, since their data hasn't changed
+pub async fn rotate(
+    conn: &DatabaseConnection,
+    keyring: &Keyring,
+    batch_size: u64,
+) -> Result<Rotation, RotationError> {
+    let mut rotation = Rotation::default();
+    let mut after: Option<Uuid> = None;
+
+    loop {
+        let txn = conn.begin().await?;
+        txn.execute_unprepared(SKIP_TOUCH).await?;
+
+        let mut select = user::Entity::find()
+            .select_only()
+            .columns([
+                user::Column::UserId,
+                user::Column::Auth0Id,
+                user::Column::Auth0IdIndex,
+                user::Column::FirstName,
+                user::Column::LastName,
+            ])
+            .order_by_asc(user::Column::UserId)
+            .limit(batch_size)
+            .lock_exclusive();
+
+        if let Some(after) = after {
+            select = select.filter(user::Column::UserId.gt(after));
+        }
+
+        let users = select.into_model::<StoredUser>().all(&txn).await?;
+
+        for stored in &users {
+            let Some(rotated) = reencrypt(keyring, stored)? else {
+                continue;
+            };
+
+            user::Entity::update_many()
+                .col_expr(user::Column::Auth0Id, Expr::value(rotated.auth0_id))
+                .col_expr(
+                    user::Column::Auth0IdIndex,
+                    Expr::value(rotated.auth0_id_index),
+                )
+                .col_expr(user::Column::FirstName, Expr::value(rotated.first_name))
+                .col_expr(user::Column::LastName, Expr::value(rotated.last_name))
+                .filter(user::Column::UserId.eq(stored.user_id))
+                .exec(&txn)
+                .await?;
+
+            rotation.rotated += 1;
+        }
+
+        txn.commit().await?;
+
+        rotation.scanned += users.len();
+        tracing::info!(
+            "scanned {} users, re-encrypted {}",
+            rotation.scanned,
+            rotation.rotated
+        );
+
+        match users.last() {
+            Some(last) if users.len() as u64 == batch_size => after = Some(last.user_id),
+            _ => return Ok(rotation),
+        }
+    }
+}
+
+// the row encrypted with the current key, or None when it already is
+pub fn reencrypt(
+    keyring: &Keyring,
+    stored: &StoredUser,
+) -> Result<Option<StoredUser>, RotationError> {
+    let current = Some(keyring.current_version());
+
+    let decrypt = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|value| keyring.decrypt(value))
+            .transpose()
+            .map_err(|err| RotationError::Decrypt(stored.user_id, err))
+    };
+
+    let auth0_id = decrypt(&stored.auth0_id)?;
+    let auth0_id_index = auth0_id
+        .as_deref()
+        .map(|auth0_id| keyring.blind_index(auth0_id));
+
+    let stale = [&stored.auth0_id, &stored.first_name, &stored.last_name]
+        .into_iter()
+        .flatten()
+        .any(|value| encryption::version(value) != current)
+        || stored.auth0_id_index != auth0_id_index;
+
+    if !stale {
+        return Ok(None);
+    }
+
+    let encrypt = |value: Option<String>| value.map(|value| keyring.encrypt(&value));
+
+    Ok(Some(StoredUser {
+        user_id: stored.user_id,
+        auth0_id: encrypt(auth0_id),
+        auth0_id_index,
+        first_name: encrypt(decrypt(&stored.first_name)?),
+        last_name: encrypt(decrypt(&stored.last_name)?),
+    }))
+}
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+    use uuid::Uuid;
+
+    use crate::encryption::{version, Keyring};
+    use crate::key_rotation::{reencrypt, rotate, Rotation, StoredUser, SKIP_TOUCH};
+
+    const KEY_1: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+    const KEY_2: &str = "ICEiIyQlJicoKSorLC0uLzAxMjM0NTY3ODk6Ozw9Pj8=";
+
+    fn stored(keyring: Option<&Keyring>) -> StoredUser {
+        let encrypt = |value: &str| match keyring {
+            Some(keyring) => keyring.encrypt(value),
+            None => value.to_owned(),
+        };
+
+        StoredUser {
+            user_id: Uuid::new_v4(),
+            auth0_id: Some(encrypt("auth0|ada")),
+            auth0_id_index: keyring.map(|keyring| keyring.blind_index("auth0|ada")),
+            first_name: Some(encrypt("Ada")),
+            last_name: None,
+        }
+    }
+
+    fn row(stored: &StoredUser) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([
+            ("user_id", stored.user_id.into()),
+            ("auth0_id", stored.auth0_id.clone().into()),
+            ("auth0_id_index", stored.auth0_id_index.clone().into()),
+            ("first_name", stored.first_name.clone().into()),
+            ("last_name", stored.last_name.clone().into()),
+        ])
+    }
+
+    #[test]
+    fn test_reencrypt() {
+        let old: Keyring = format!("1:{KEY_1}").parse().unwrap();
+        let keyring: Keyring = format!("2:{KEY_2},1:{KEY_1}").parse().unwrap();
+
+        // already on the current key
+        assert!(reencrypt(&keyring, &stored(Some(&keyring)))
+            .unwrap()
+            .is_none());
+
+        for stale in [stored(None), stored(Some(&old))] {
+            let rotated = reencrypt(&keyring, &stale).unwrap().unwrap();
+
+            assert_eq!(rotated.user_id, stale.user_id);
+            assert_eq!(version(rotated.auth0_id.as_deref().unwrap()), Some(2));
+            assert_eq!(version(rotated.first_name.as_deref().unwrap()), Some(2));
+            assert_eq!(rotated.last_name, None);
+            assert_eq!(
+                rotated.auth0_id_index,
+                Some(keyring.blind_index("auth0|ada"))
+            );
+            assert_eq!(
+                keyring.decrypt(rotated.first_name.as_deref().unwrap()),
+                Ok("Ada".to_owned())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotate_in_batches() {
+        let keyring: Keyring = format!("2:{KEY_2},1:{KEY_1}").parse().unwrap();
+
+        let first = [stored(None), stored(Some(&keyring))];
+        let second = [stored(None)];
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([first.iter().map(row).collect::<Vec<_>>()])
+            .append_query_results([second.iter().map(row).collect::<Vec<_>>()])
+            .append_exec_results((0..4).map(|_| MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }))
+            .into_connection();
+
+        let rotation = rotate(&conn, &keyring, 2).await.unwrap();
+
+        assert_eq!(
+            rotation,
+            Rotation {
+                scanned: 3,
+                rotated: 2
+            }
+        );
+
+        let log = format!("{:?}", conn.into_transaction_log());
+        assert_eq!(log.matches("FOR UPDATE").count(), 2, "{log}");
+        assert_eq!(log.matches("UPDATE \\\"users\\\"").count(), 2, "{log}");
+        assert_eq!(log.matches("COMMIT").count(), 2, "{log}");
+    }
+
+    // the triggers would otherwise stamp updated_at and bump the version of
+    // every re-encrypted row, changing its ETag though its data is the same
+    #[tokio::test]
+    async fn test_rotate_keeps_updated_at_and_version() {
+        let keyring: Keyring = format!("2:{KEY_2},1:{KEY_1}").parse().unwrap();
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![row(&stored(None))]])
+            .append_exec_results((0..2).map(|_| MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }))
+            .into_connection();
+
+        rotate(&conn, &keyring, 2).await.unwrap();
+
+        let log = format!("{:?}", conn.into_transaction_log());
+        let skip = log.find(SKIP_TOUCH).expect(&log);
+        let update = log.find("UPDATE \\\"users\\\"").expect(&log);
+        assert!(skip < update, "{log}");
+        assert!(!log.contains("updated_at"), "{log}");
+        assert!(!log.contains("version"), "{log}");
+    }
+}
@@ -0,0 +1,145 @@
+#[path = "logging_test.rs"]
+#[cfg(test)]
+mod logging_test;
+
+use std::io::{self, Write};
+use std::sync::LazyLock;
+
+use axum::http::{header, HeaderMap};
+use regex::Regex;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+pub const DEFAULT_FILTER: &str = "info";
+pub const REDACTED: &str = "[redacted]";
+
+// headers whose values are never logged
+const SENSITIVE_HEADERS: [header::HeaderName; 4] = [
+    header::AUTHORIZATION,
+    header::PROXY_AUTHORIZATION,
+    header::COOKIE,
+    header::SET_COOKIE,
+];
+
+// bearer tokens, bare JWTs and passwords in connection urls
+static SECRET_PATTERNS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    vec![
+        (
+            Regex::new(r"(?i)(bearer\s+)[A-Za-z0-9\-._~+/]+=*").unwrap(),
+            "${1}[redacted]",
+        ),
+        (
+            Regex::new(r"eyJ[A-Za-z0-9_-]*\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*").unwrap(),
+            "[redacted]",
+        ),
+        (
+            Regex::new(r"(?i)((?:access|refresh|id)_token\\?[\x22']?\s*[:=]\s*\\?[\x22']?)[^\s\x22'\\,&}]+")
+                .unwrap(),
+            "${1}[redacted]",
+        ),
+        (
+            Regex::new(r"(://[^:/@\s]+:)[^@\s]+@").unwrap(),
+            "${1}[redacted]@",
+        ),
+    ]
+});
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(format: &str) -> Option<LogFormat> {
+        match format.to_lowercase().as_str() {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn redact(line: &str) -> String {
+    SECRET_PATTERNS
+        .iter()
+        .fold(line.to_owned(), |line, (pattern, replacement)| {
+            pattern.replace_all(&line, *replacement).into_owned()
+        })
+}
+
+pub fn redacted_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match SENSITIVE_HEADERS.contains(name) {
+                true => REDACTED.to_owned(),
+                false => redact(&String::from_utf8_lossy(value.as_bytes())),
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+// scrubs secrets from every formatted line before it reaches the underlying
+// writer, as a backstop for values that were logged by mistake
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        RedactingMakeWriter { inner }
+    }
+}
+
+pub struct RedactingWriter<W> {
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    // the fmt layer writes each formatted event with a single write_all
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let line = redact(&String::from_utf8_lossy(buf));
+        self.inner.write_all(line.as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+// the formatting layer; span fields such as the request id are included on
+// every line in both formats
+pub fn fmt_layer<S, M>(format: LogFormat, writer: M) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    M: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let writer = RedactingMakeWriter::new(writer);
+
+    match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
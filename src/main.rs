@@ -1,78 +1,180 @@
 #![allow(dead_code)]
 
 use anyhow::anyhow;
+use clap::Parser;
+use config::{AuthorizationEngine, CommandConfig, Config, ConfigError};
 use handlers::AppState;
-use std::{env, sync::Arc};
-use tower_http::trace::TraceLayer;
+use std::{path::PathBuf, sync::Arc};
 
 mod auth0;
 mod authentication;
 mod authorization;
+mod commands;
+mod config;
+mod database;
+mod encryption;
 mod errors;
 mod handlers;
+mod key_rotation;
+mod logging;
+mod metrics;
+mod migrations;
 mod models;
+mod purge;
+mod seed;
+mod shutdown;
+mod telemetry;
+mod validation;
 
 #[cfg(test)]
 mod test_utils;
 
+#[derive(Parser)]
+#[command(about = "Users and groups API server")]
+struct Cli {
+    /// TOML file to read settings from, environment variables take precedence
+    #[arg(long, env = "CONFIG_FILE")]
+    config: Option<PathBuf>,
+
+    /// Validate the configuration, print it with secrets redacted and exit
+    #[arg(long)]
+    check_config: bool,
+
+    /// Run a command instead of the web server
+    #[command(subcommand)]
+    command: Option<commands::Command>,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<(), anyhow::Error> {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::DEBUG)
-        .init();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(command) => {
+            let config = load_or_exit(CommandConfig::load(cli.config.as_deref()));
+            if cli.check_config {
+                println!("{config:#?}");
+                return Ok(());
+            }
+            commands::run(config, command).await
+        }
+        None => {
+            let config = load_or_exit(Config::load(cli.config.as_deref()));
+            if cli.check_config {
+                println!("{config:#?}");
+                return Ok(());
+            }
+            serve(config).await
+        }
+    }
+}
 
-    tracing::info!("initializing the web server...");
+// reports every configuration error before anything starts
+fn load_or_exit<T>(config: Result<T, ConfigError>) -> T {
+    config.unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    })
+}
+
+async fn serve(config: Config) -> anyhow::Result<()> {
+    let telemetry = telemetry::init(
+        config.telemetry.otlp_endpoint.as_deref(),
+        &config.telemetry.service_name,
+        config.telemetry.log_format,
+    )?;
 
-    let db_name = env::var("DB_NAME").expect("DB_NAME must be set");
-    let db_port = env::var("DB_PORT").expect("DB_PORT must be set");
-    let db_host = env::var("DB_HOST").expect("DB_HOST must be set");
-    let db_user = env::var("DB_USER").expect("DB_USER must be set");
-    let db_password = env::var("DB_PASSWORD").expect("DB_PASSWORD must be set");
+    tracing::info!("initializing the web server...");
 
-    let db_url = format!("postgres://{db_user}:{db_password}@{db_host}:{db_port}/{db_name}");
+    match config.encryption.keyring.clone() {
+        Some(keyring) => encryption::init(keyring),
+        None => tracing::warn!("ENCRYPTION_KEY isn't set, personal data is stored in plaintext"),
+    }
 
     tracing::info!("connecting to database");
 
-    let conn = sea_orm::Database::connect(&db_url).await.map_err(|err| {
-        tracing::error!("error connecting to the database: {:?}", err);
-        anyhow!(err)
-    })?;
+    let mut conn = sea_orm::Database::connect(database::connect_options(&config.database)?)
+        .await
+        .map_err(|err| {
+            tracing::error!("error connecting to the database: {:?}", err);
+            anyhow!(err)
+        })?;
 
     tracing::info!("connected to database");
 
-    let auth0_domain = std::env::var("AUTH0_DOMAIN").expect("AUTH0_DOMAIN must be set");
-    let auth0_audience = std::env::var("AUTH0_AUDIENCE").expect("AUTH0_AUDIENCE must be set");
-
-    let auth = authentication::Authentication {
-        audience: auth0_audience,
-        domain: auth0_domain,
+    if config.database.migrate_on_startup {
+        let applied = migrations::up(&conn).await?;
+        tracing::info!("applied {} migrations", applied.len());
+    }
+
+    conn.set_metric_callback(|info| {
+        metrics::get().observe_query(&info.statement.sql, info.failed, info.elapsed);
+        telemetry::record_query(info);
+    });
+
+    let auth = authentication::Authentication::new(
+        config.auth0.audience.clone(),
+        config.auth0.domain.clone(),
+    );
+
+    tracing::info!("loading jwks");
+
+    if let Err(err) = auth.warm_up().await {
+        tracing::warn!("error loading jwks, will retry on first request: {}", err);
+    }
+
+    let authz: Arc<dyn authorization::IAuthorization> = match config.authorization.engine {
+        AuthorizationEngine::Builtin => Arc::new(authorization::Authorization {}),
+        AuthorizationEngine::Oso => {
+            // policy files can be supplied at runtime, otherwise the bundled policies are used
+            let policy_files = config.authorization.policy_files.clone();
+            let oso_authz = match policy_files.is_empty() {
+                false => authorization::oso::OsoAuthorizationClient::from_files(policy_files),
+                true => authorization::oso::OsoAuthorizationClient::from_default_policies(),
+            }
+            .map_err(|err| {
+                tracing::error!("error loading authorization policies: {:?}", err);
+                anyhow!("error loading authorization policies")
+            })?;
+            Arc::new(oso_authz)
+        }
     };
 
-    let authz = authorization::Authorization {};
+    tracing::info!("using {:?} authorization", config.authorization.engine);
+
+    let conn = Arc::new(conn);
 
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "7000".to_owned())
-        .parse::<u16>()
-        .expect("PORT must be a number");
+    purge::spawn(conn.clone(), config.retention.clone());
+
+    let readiness = shutdown::Readiness::default();
 
     let app_state = AppState {
         authentication: Arc::new(auth),
-        authorization: Arc::new(authz),
-        conn: Arc::new(conn),
+        authorization: Arc::new(authorization::metered::MeteredAuthorization::new(authz)),
+        conn: conn.clone(),
+        readiness: readiness.clone(),
     };
 
     tracing::info!("starting the web server...");
 
-    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", config.server.port))
         .await
         .map_err(|err| anyhow!(err))?;
 
-    axum::serve(
+    let res = shutdown::serve(
         listener,
-        handlers::router(app_state)
-            .layer(TraceLayer::new_for_http())
-            .into_make_service(),
+        handlers::router_with_config(app_state, &config.cors, &config.docs),
+        readiness,
+        &config.shutdown,
+        shutdown::signal(),
     )
     .await
-    .map_err(|err| anyhow!(err))
+    .map_err(|err| anyhow!(err));
+
+    tracing::info!("closing the database pool");
+    conn.get_postgres_connection_pool().close().await;
+
+    telemetry.shutdown();
+
+    res
 }
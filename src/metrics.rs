@@ -0,0 +1,163 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+// the label used for requests that did not match any route, so scanners
+// probing random paths can't blow up the number of series
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn get() -> &'static Metrics {
+    &METRICS
+}
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests_total: IntCounterVec,
+    pub http_request_duration_seconds: HistogramVec,
+    pub db_query_duration_seconds: HistogramVec,
+    pub jwks_fetch_duration_seconds: HistogramVec,
+    pub jwks_fetch_failures_total: IntCounter,
+    pub authorization_denials_total: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        let registry = Registry::new();
+
+        let http_requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let db_query_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "db_query_duration_seconds",
+                "Database query latency in seconds",
+            )
+            .buckets(vec![
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+            ]),
+            &["operation", "outcome"],
+        )
+        .unwrap();
+        let jwks_fetch_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "jwks_fetch_duration_seconds",
+                "JWKS fetch latency in seconds",
+            ),
+            &["outcome"],
+        )
+        .unwrap();
+        let jwks_fetch_failures_total = IntCounter::new(
+            "jwks_fetch_failures_total",
+            "JWKS fetches that failed or returned an invalid key set",
+        )
+        .unwrap();
+        let authorization_denials_total = IntCounterVec::new(
+            Opts::new(
+                "authorization_denials_total",
+                "Authorization checks that denied the actor",
+            ),
+            &["check"],
+        )
+        .unwrap();
+
+        registry
+            .register(Box::new(http_requests_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(http_request_duration_seconds.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(db_query_duration_seconds.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(jwks_fetch_duration_seconds.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(jwks_fetch_failures_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(authorization_denials_total.clone()))
+            .unwrap();
+
+        Metrics {
+            registry,
+            http_requests_total,
+            http_request_duration_seconds,
+            db_query_duration_seconds,
+            jwks_fetch_duration_seconds,
+            jwks_fetch_failures_total,
+            authorization_denials_total,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.http_requests_total.with_label_values(&labels).inc();
+        self.http_request_duration_seconds
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_query(&self, statement: &str, failed: bool, elapsed: Duration) {
+        let operation = query_operation(statement);
+        let outcome = if failed { "error" } else { "ok" };
+        self.db_query_duration_seconds
+            .with_label_values(&[operation.as_str(), outcome])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_jwks_fetch(&self, succeeded: bool, elapsed: Duration) {
+        let outcome = if succeeded { "ok" } else { "error" };
+        self.jwks_fetch_duration_seconds
+            .with_label_values(&[outcome])
+            .observe(elapsed.as_secs_f64());
+        if !succeeded {
+            self.jwks_fetch_failures_total.inc();
+        }
+    }
+
+    pub fn observe_denial(&self, check: &str) {
+        self.authorization_denials_total
+            .with_label_values(&[check])
+            .inc();
+    }
+
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(err) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("error encoding metrics: {:?}", err);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+// only the leading keyword is used as a label, the full statement would give
+// every distinct query its own series
+fn query_operation(statement: &str) -> String {
+    match statement.split_whitespace().next() {
+        Some(keyword) => match keyword.to_lowercase().as_str() {
+            operation @ ("select" | "insert" | "update" | "delete" | "begin" | "commit"
+            | "rollback") => operation.to_owned(),
+            _ => "other".to_owned(),
+        },
+        None => "other".to_owned(),
+    }
+}
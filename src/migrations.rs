@@ -0,0 +1,400 @@
+#[path = "migrations_test.rs"]
+#[cfg(test)]
+mod migrations_test;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, DbErr, FromQueryResult,
+    Statement, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+// an arbitrary key for pg_advisory_xact_lock, shared by every instance so
+// concurrent runs apply migrations one at a time
+const LOCK_KEY: i64 = 0x7573_6572_735f_6d67;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+impl Migration {
+    // only the up script is checksummed, down scripts may be fixed afterwards
+    pub fn checksum(&self) -> String {
+        format!("{:x}", Sha256::digest(self.up.as_bytes()))
+    }
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!(
+                "../migrations/",
+                stringify!($version),
+                "_",
+                $name,
+                ".up.sql"
+            )),
+            down: include_str!(concat!(
+                "../migrations/",
+                stringify!($version),
+                "_",
+                $name,
+                ".down.sql"
+            )),
+        }
+    };
+}
+
+// every file in migrations/, in version order
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(20200101000000, "create_uuid_extension"),
+    migration!(20200417000001, "create_users"),
+    migration!(20200417000201, "create_groups"),
+    migration!(20200417000301, "create_group_users"),
+    migration!(20261018000001, "create_group_users_unique_index"),
+    migration!(20261018000002, "add_group_users_role"),
+    migration!(20261018000003, "add_users_auth0_id_index"),
+    migration!(20261018000004, "add_users_deleted_at"),
+    migration!(20261018000005, "add_updated_at_triggers"),
+    migration!(20261018000006, "add_version"),
+    migration!(20261018000007, "add_skip_touch_setting"),
+    migration!(20261018000008, "drop_users_auth0_id_key"),
+];
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    #[error("migration {0} was changed after it was applied")]
+    ChecksumMismatch(i64),
+    #[error("migration {0} is applied but isn't known to this binary")]
+    Unknown(i64),
+}
+
+#[derive(Debug, FromQueryResult)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub checksum: String,
+    pub applied_at: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MigrationState {
+    Applied(DateTimeWithTimeZone),
+    Pending,
+    // applied, but the embedded up script no longer matches
+    Modified(DateTimeWithTimeZone),
+    // applied by a newer binary, or removed since
+    Unknown(DateTimeWithTimeZone),
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: &'static str,
+    pub state: MigrationState,
+}
+
+impl fmt::Display for MigrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match &self.state {
+            MigrationState::Applied(at) => format!("applied {}", at.to_rfc3339()),
+            MigrationState::Pending => "pending".to_owned(),
+            MigrationState::Modified(at) => format!("modified, applied {}", at.to_rfc3339()),
+            MigrationState::Unknown(at) => format!("unknown, applied {}", at.to_rfc3339()),
+        };
+        write!(f, "{} {:<40} {}", self.version, self.name, state)
+    }
+}
+
+// applies every pending migration in a single transaction, so a failing
+// migration leaves the schema untouched; migrations applied by a newer release
+// are only warned about, so instances still running the old release during a
+// rolling deploy or after a rollback can start
+pub async fn up(conn: &DatabaseConnection) -> Result<Vec<&'static Migration>, MigrationError> {
+    let txn = begin(conn).await?;
+
+    let applied = applied(&txn).await?;
+    for version in verify_known(&applied)? {
+        tracing::warn!(
+            "migration {} is applied but isn't known to this binary, it was probably applied by a newer release",
+            version
+        );
+    }
+
+    let pending = pending(&applied);
+
+    for migration in &pending {
+        tracing::info!(
+            "applying migration {} {}",
+            migration.version,
+            migration.name
+        );
+
+        txn.execute_unprepared(migration.up).await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "insert into schema_migrations (version, name, checksum) values ($1, $2, $3)",
+            [
+                migration.version.into(),
+                migration.name.into(),
+                migration.checksum().into(),
+            ],
+        ))
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(pending)
+}
+
+// reverts the `steps` most recently applied migrations, newest first
+pub async fn down(
+    conn: &DatabaseConnection,
+    steps: usize,
+) -> Result<Vec<&'static Migration>, MigrationError> {
+    let txn = begin(conn).await?;
+
+    let applied = applied(&txn).await?;
+    verify(&applied)?;
+
+    let reverted = applied
+        .iter()
+        .rev()
+        .take(steps)
+        .filter_map(|applied| find(applied.version))
+        .collect::<Vec<&'static Migration>>();
+
+    for migration in &reverted {
+        tracing::info!(
+            "reverting migration {} {}",
+            migration.version,
+            migration.name
+        );
+
+        txn.execute_unprepared(migration.down).await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "delete from schema_migrations where version = $1",
+            [migration.version.into()],
+        ))
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(reverted)
+}
+
+pub async fn status(conn: &DatabaseConnection) -> Result<Vec<MigrationStatus>, MigrationError> {
+    let exists = table_exists(conn, "schema_migrations").await?;
+
+    let applied = match exists {
+        true => applied(conn).await?,
+        false => Vec::new(),
+    };
+
+    Ok(statuses(&applied))
+}
+
+// takes the lock and creates the tracking table; databases migrated by
+// sqlx-cli have their applied versions adopted the first time
+async fn begin(conn: &DatabaseConnection) -> Result<DatabaseTransaction, MigrationError> {
+    let txn = conn.begin().await?;
+
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "select pg_advisory_xact_lock($1)",
+        [LOCK_KEY.into()],
+    ))
+    .await?;
+
+    let exists = table_exists(&txn, "schema_migrations").await?;
+
+    if !exists {
+        txn.execute_unprepared(
+            "create table schema_migrations (
+              version bigint primary key,
+              name text not null,
+              checksum text not null,
+              applied_at timestamptz not null default (now())
+            )",
+        )
+        .await?;
+
+        if table_exists(&txn, "_sqlx_migrations").await? {
+            adopt_sqlx_migrations(&txn).await?;
+        }
+    }
+
+    Ok(txn)
+}
+
+async fn adopt_sqlx_migrations(txn: &DatabaseTransaction) -> Result<(), MigrationError> {
+    #[derive(FromQueryResult)]
+    struct SqlxMigration {
+        version: i64,
+    }
+
+    let versions = SqlxMigration::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "select version from _sqlx_migrations where success order by version",
+    ))
+    .all(txn)
+    .await?;
+
+    for version in versions {
+        let Some(migration) = find(version.version) else {
+            continue;
+        };
+
+        tracing::info!(
+            "adopting migration {} {} applied by sqlx",
+            migration.version,
+            migration.name
+        );
+
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "insert into schema_migrations (version, name, checksum) values ($1, $2, $3)",
+            [
+                migration.version.into(),
+                migration.name.into(),
+                migration.checksum().into(),
+            ],
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn table_exists(conn: &impl ConnectionTrait, table: &str) -> Result<bool, DbErr> {
+    #[derive(FromQueryResult)]
+    struct Exists {
+        exists: bool,
+    }
+
+    let exists = Exists::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "select to_regclass($1) is not null as exists",
+        [table.into()],
+    ))
+    .one(conn)
+    .await?;
+
+    Ok(exists.is_some_and(|exists| exists.exists))
+}
+
+async fn applied(conn: &impl ConnectionTrait) -> Result<Vec<AppliedMigration>, DbErr> {
+    AppliedMigration::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "select version, checksum, applied_at from schema_migrations order by version",
+    ))
+    .all(conn)
+    .await
+}
+
+fn find(version: i64) -> Option<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .find(|migration| migration.version == version)
+}
+
+pub fn verify(applied: &[AppliedMigration]) -> Result<(), MigrationError> {
+    match verify_known(applied)?.first() {
+        Some(version) => Err(MigrationError::Unknown(*version)),
+        None => Ok(()),
+    }
+}
+
+// checks the applied migrations this binary knows, returning the versions of
+// the ones it doesn't
+pub fn verify_known(applied: &[AppliedMigration]) -> Result<Vec<i64>, MigrationError> {
+    let mut unknown = Vec::new();
+
+    for applied in applied {
+        let Some(migration) = find(applied.version) else {
+            unknown.push(applied.version);
+            continue;
+        };
+
+        if migration.checksum() != applied.checksum {
+            return Err(MigrationError::ChecksumMismatch(applied.version));
+        }
+    }
+
+    Ok(unknown)
+}
+
+pub fn pending(applied: &[AppliedMigration]) -> Vec<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .filter(|migration| {
+            !applied
+                .iter()
+                .any(|applied| applied.version == migration.version)
+        })
+        .collect()
+}
+
+pub fn statuses(applied: &[AppliedMigration]) -> Vec<MigrationStatus> {
+    let applied_by_version = applied
+        .iter()
+        .map(|applied| (applied.version, applied))
+        .collect::<HashMap<i64, &AppliedMigration>>();
+
+    let mut statuses = MIGRATIONS
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            name: migration.name,
+            state: match applied_by_version.get(&migration.version) {
+                Some(applied) if applied.checksum == migration.checksum() => {
+                    MigrationState::Applied(applied.applied_at)
+                }
+                Some(applied) => MigrationState::Modified(applied.applied_at),
+                None => MigrationState::Pending,
+            },
+        })
+        .collect::<Vec<MigrationStatus>>();
+
+    statuses.extend(
+        applied
+            .iter()
+            .filter(|applied| find(applied.version).is_none())
+            .map(|applied| MigrationStatus {
+                version: applied.version,
+                name: "",
+                state: MigrationState::Unknown(applied.applied_at),
+            }),
+    );
+
+    statuses.sort_by_key(|status| status.version);
+    statuses
+}
+
+// the checks `down` makes, for `migrate status --strict`
+pub fn verify_statuses(statuses: &[MigrationStatus]) -> Result<(), MigrationError> {
+    for status in statuses {
+        match status.state {
+            MigrationState::Modified(_) => {
+                return Err(MigrationError::ChecksumMismatch(status.version))
+            }
+            MigrationState::Unknown(_) => return Err(MigrationError::Unknown(status.version)),
+            MigrationState::Applied(_) | MigrationState::Pending => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+
+    use crate::migrations::{
+        pending, statuses, up, verify, verify_known, verify_statuses, AppliedMigration,
+        MigrationError, MigrationState, MIGRATIONS,
+    };
+
+    fn applied(version: i64, checksum: String) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            checksum,
+            applied_at: chrono::Utc::now().into(),
+        }
+    }
+
+    fn exists(exists: bool) -> Vec<BTreeMap<&'static str, Value>> {
+        vec![BTreeMap::from([("exists", Value::Bool(Some(exists)))])]
+    }
+
+    // a migration added to migrations/ but not to MIGRATIONS would never run
+    #[test]
+    fn test_migrations_match_files() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+
+        let mut files = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<String>>();
+        files.sort();
+
+        let expected = MIGRATIONS
+            .iter()
+            .flat_map(|migration| {
+                [
+                    format!("{}_{}.down.sql", migration.version, migration.name),
+                    format!("{}_{}.up.sql", migration.version, migration.name),
+                ]
+            })
+            .collect::<Vec<String>>();
+
+        assert_eq!(files, expected);
+        assert!(MIGRATIONS
+            .windows(2)
+            .all(|pair| pair[0].version < pair[1].version));
+    }
+
+    #[test]
+    fn test_verify() {
+        let first = &MIGRATIONS[0];
+
+        assert!(verify(&[applied(first.version, first.checksum())]).is_ok());
+
+        assert!(matches!(
+            verify(&[applied(first.version, "changed".to_owned())]),
+            Err(MigrationError::ChecksumMismatch(version)) if version == first.version
+        ));
+
+        assert!(matches!(
+            verify(&[applied(1, first.checksum())]),
+            Err(MigrationError::Unknown(1))
+        ));
+
+        // unknown migrations are returned rather than failing, changed ones
+        // still fail
+        assert_eq!(
+            verify_known(&[
+                applied(first.version, first.checksum()),
+                applied(1, first.checksum())
+            ])
+            .unwrap(),
+            vec![1]
+        );
+        assert!(matches!(
+            verify_known(&[applied(first.version, "changed".to_owned()), applied(1, first.checksum())]),
+            Err(MigrationError::ChecksumMismatch(version)) if version == first.version
+        ));
+    }
+
+    #[test]
+    fn test_statuses() {
+        let (first, second) = (&MIGRATIONS[0], &MIGRATIONS[1]);
+        let applied = [
+            applied(first.version, first.checksum()),
+            applied(second.version, "changed".to_owned()),
+            applied(99990101000000, "newer".to_owned()),
+        ];
+
+        let statuses = statuses(&applied);
+
+        assert_eq!(statuses.len(), MIGRATIONS.len() + 1);
+        assert!(matches!(statuses[0].state, MigrationState::Applied(_)));
+        assert!(matches!(statuses[1].state, MigrationState::Modified(_)));
+        assert!(statuses[2..MIGRATIONS.len()]
+            .iter()
+            .all(|status| status.state == MigrationState::Pending));
+        assert!(matches!(
+            statuses[MIGRATIONS.len()].state,
+            MigrationState::Unknown(_)
+        ));
+
+        assert_eq!(pending(&applied).len(), MIGRATIONS.len() - 2);
+
+        assert!(matches!(
+            verify_statuses(&statuses),
+            Err(MigrationError::ChecksumMismatch(version)) if version == second.version
+        ));
+        assert!(matches!(
+            verify_statuses(&statuses[2..]),
+            Err(MigrationError::Unknown(99990101000000))
+        ));
+        assert!(verify_statuses(&statuses[..1]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_up_applies_pending_in_order() {
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            // schema_migrations and _sqlx_migrations don't exist, nothing is applied
+            .append_query_results([exists(false), exists(false), vec![]])
+            .append_exec_results((0..2 + 2 * MIGRATIONS.len()).map(|_| MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }))
+            .into_connection();
+
+        let applied = up(&conn).await.unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+
+        let log = format!("{:?}", conn.into_transaction_log());
+
+        assert!(log.contains("pg_advisory_xact_lock"));
+        assert!(log.contains("create table schema_migrations"));
+
+        let positions = MIGRATIONS
+            .iter()
+            .map(|migration| {
+                let up = format!("{:?}", migration.up);
+                log.find(up.trim_matches('"'))
+                    .unwrap_or_else(|| panic!("{} wasn't applied", migration.name))
+            })
+            .collect::<Vec<usize>>();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(log.rfind("COMMIT") > positions.last().copied());
+    }
+
+    // an older release starting after a newer one migrated, e.g. during a
+    // rolling deploy, only warns about the migrations it doesn't know
+    #[tokio::test]
+    async fn test_up_allows_unknown_applied() {
+        let applied = MIGRATIONS
+            .iter()
+            .map(|migration| (migration.version, migration.checksum()))
+            .chain([(99990101000000, "newer".to_owned())])
+            .map(|(version, checksum)| {
+                BTreeMap::from([
+                    ("version", Value::BigInt(Some(version))),
+                    ("checksum", Value::String(Some(Box::new(checksum)))),
+                    (
+                        "applied_at",
+                        Value::ChronoDateTimeWithTimeZone(Some(Box::new(
+                            chrono::Utc::now().into(),
+                        ))),
+                    ),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([exists(true), applied])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        assert!(up(&conn).await.unwrap().is_empty());
+    }
+}
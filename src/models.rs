@@ -1,3 +1,39 @@
+#[path = "models_test.rs"]
+#[cfg(test)]
+mod models_test;
+
 pub mod group;
 pub mod group_user;
 pub mod user;
+
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::ActiveValue;
+
+// stamps the timestamps of a row about to be saved: both on insert, unless
+// created_at was given, and only updated_at on update; the set_updated_at
+// trigger does the same for writes that bypass the models
+fn stamp(
+    created_at: &mut ActiveValue<DateTimeWithTimeZone>,
+    updated_at: &mut ActiveValue<DateTimeWithTimeZone>,
+    insert: bool,
+) {
+    let now: DateTimeWithTimeZone = chrono::Utc::now().into();
+
+    if insert && created_at.is_not_set() {
+        *created_at = ActiveValue::Set(now);
+    }
+    *updated_at = ActiveValue::Set(now);
+}
+
+// bumps the version of a row about to be updated, which updates guard on so
+// concurrent writes can't overwrite each other; the bump_version trigger does
+// the same for writes that bypass the models
+fn bump(version: &mut ActiveValue<i32>, insert: bool) {
+    if insert {
+        return;
+    }
+
+    if let ActiveValue::Unchanged(current) | ActiveValue::Set(current) = version {
+        *version = ActiveValue::Set(*current + 1);
+    }
+}
@@ -11,9 +11,41 @@ pub struct Model {
     pub created_at: chrono::DateTime<chrono::FixedOffset>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
     pub updated_at: chrono::DateTime<chrono::FixedOffset>,
+    // bumped on every update, the ETag is derived from it
+    pub version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::group_user::Entity")]
+    GroupUser,
+}
+
+impl Related<super::group_user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GroupUser.def()
+    }
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::group_user::Relation::User.def()
+    }
 
-impl ActiveModelBehavior for ActiveModel {}
+    fn via() -> Option<RelationDef> {
+        Some(super::group_user::Relation::Group.def().rev())
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        super::stamp(&mut self.created_at, &mut self.updated_at, insert);
+        super::bump(&mut self.version, insert);
+
+        Ok(self)
+    }
+}
@@ -1,20 +1,76 @@
 use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue;
 use uuid::Uuid;
 
+use crate::encryption::{self, Encrypted};
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "users")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub user_id: Uuid,
-    pub auth0_id: Option<String>,
-    pub first_name: Option<String>,
-    pub last_name: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub auth0_id: Option<Encrypted>,
+    // looked up instead of auth0_id, which is encrypted with a random nonce
+    pub auth0_id_index: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub first_name: Option<Encrypted>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_name: Option<Encrypted>,
     pub role: String,
     pub created_at: chrono::DateTime<chrono::FixedOffset>,
     pub updated_at: chrono::DateTime<chrono::FixedOffset>,
+    // set when the user is deleted, the row is purged after the retention period
+    pub deleted_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    // bumped on every update, the ETag is derived from it
+    pub version: i32,
+}
+
+// users that haven't been deleted, which every query but restoring and
+// purging wants
+pub fn find_active() -> Select<Entity> {
+    Entity::find().filter(Column::DeletedAt.is_null())
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::group_user::Entity")]
+    GroupUser,
+}
+
+impl Related<super::group_user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GroupUser.def()
+    }
+}
 
-impl ActiveModelBehavior for ActiveModel {}
+impl Related<super::group::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::group_user::Relation::Group.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::group_user::Relation::User.def().rev())
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        super::stamp(&mut self.created_at, &mut self.updated_at, insert);
+        super::bump(&mut self.version, insert);
+
+        if let ActiveValue::Set(auth0_id) = &self.auth0_id {
+            self.auth0_id_index = ActiveValue::Set(
+                auth0_id
+                    .as_ref()
+                    .map(|auth0_id| encryption::blind_index(&auth0_id.0)),
+            );
+        }
+
+        Ok(self)
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use sea_orm::prelude::DateTimeWithTimeZone;
+    use sea_orm::{
+        ActiveModelBehavior, ActiveModelTrait, ActiveValue, DatabaseBackend, DatabaseConnection,
+        IntoActiveModel, MockDatabase,
+    };
+    use uuid::Uuid;
+
+    use crate::models::{group, group_user, user};
+
+    fn created_at() -> DateTimeWithTimeZone {
+        "2020-04-17T00:00:00+00:00".parse().unwrap()
+    }
+
+    fn user() -> user::Model {
+        user::Model {
+            user_id: Uuid::new_v4(),
+            auth0_id: Some("auth0_id".into()),
+            auth0_id_index: None,
+            first_name: Some("first_name".into()),
+            last_name: None,
+            role: "user".to_owned(),
+            created_at: created_at(),
+            updated_at: created_at(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    fn conn() -> DatabaseConnection {
+        MockDatabase::new(DatabaseBackend::Postgres).into_connection()
+    }
+
+    // checks `stamped` was set to the time of the save
+    fn assert_stamped(stamped: ActiveValue<DateTimeWithTimeZone>, before: DateTimeWithTimeZone) {
+        let ActiveValue::Set(stamped) = stamped else {
+            panic!("not stamped: {stamped:?}");
+        };
+        assert!(stamped >= before, "{stamped} < {before}");
+        assert!(stamped <= chrono::Utc::now(), "{stamped} is in the future");
+    }
+
+    #[tokio::test]
+    async fn test_user_insert_stamps_both() {
+        let before = chrono::Utc::now().into();
+
+        let user = user::ActiveModel {
+            user_id: ActiveValue::Set(Uuid::new_v4()),
+            role: ActiveValue::Set("user".to_owned()),
+            ..Default::default()
+        }
+        .before_save(&conn(), true)
+        .await
+        .unwrap();
+
+        assert_stamped(user.created_at.clone(), before);
+        assert_eq!(user.created_at, user.updated_at);
+    }
+
+    #[tokio::test]
+    async fn test_insert_keeps_given_created_at() {
+        let before = chrono::Utc::now().into();
+
+        let group = group::ActiveModel {
+            group_id: ActiveValue::Set(Uuid::new_v4()),
+            name: ActiveValue::Set("group".to_owned()),
+            created_at: ActiveValue::Set(created_at()),
+            ..Default::default()
+        }
+        .before_save(&conn(), true)
+        .await
+        .unwrap();
+
+        assert_eq!(group.created_at, ActiveValue::Set(created_at()));
+        assert_stamped(group.updated_at, before);
+    }
+
+    #[tokio::test]
+    async fn test_update_stamps_updated_at() {
+        let before = chrono::Utc::now().into();
+
+        let mut user = user().into_active_model();
+        user.first_name = ActiveValue::Set(Some("changed".into()));
+        let user = user.before_save(&conn(), false).await.unwrap();
+
+        assert_eq!(user.created_at, ActiveValue::Unchanged(created_at()));
+        assert_stamped(user.updated_at, before);
+
+        let mut group_user = group_user::Model {
+            group_user_id: Uuid::new_v4(),
+            group_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            role: "member".to_owned(),
+            created_at: created_at(),
+            updated_at: created_at(),
+        }
+        .into_active_model();
+        group_user.role = ActiveValue::Set("admin".to_owned());
+        let group_user = group_user.before_save(&conn(), false).await.unwrap();
+
+        assert_eq!(group_user.created_at, ActiveValue::Unchanged(created_at()));
+        assert_stamped(group_user.updated_at, before);
+    }
+
+    #[tokio::test]
+    async fn test_update_writes_updated_at() {
+        let user = user();
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[user.clone()]])
+            .into_connection();
+
+        let mut active = user.into_active_model();
+        active.first_name = ActiveValue::Set(Some("changed".into()));
+        active.update(&conn).await.unwrap();
+
+        let log = format!("{:?}", conn.into_transaction_log());
+        assert!(log.contains(r#"\"updated_at\" = "#), "{log}");
+        assert!(!log.contains(r#"\"created_at\" = "#), "{log}");
+    }
+}
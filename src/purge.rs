@@ -0,0 +1,130 @@
+#[path = "purge_test.rs"]
+#[cfg(test)]
+mod purge_test;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use sea_orm::sea_query::{Alias, Expr, Query};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::authorization::GROUP_ROLE_OWNER;
+use crate::config::RetentionConfig;
+use crate::models::{group_user, user};
+
+// permanently deletes users that were deleted more than `retention` ago,
+// along with their group memberships, returning how many users were purged
+pub async fn purge_deleted_users(
+    conn: &DatabaseConnection,
+    retention: Duration,
+) -> Result<u64, DbErr> {
+    // a retention too long to represent can't have passed yet
+    let Some(cutoff) = i64::try_from(retention.as_secs())
+        .ok()
+        .and_then(chrono::TimeDelta::try_seconds)
+        .and_then(|retention| chrono::Utc::now().checked_sub_signed(retention))
+    else {
+        return Ok(0);
+    };
+
+    let txn = conn.begin().await?;
+
+    // locked so a user can't be restored while being purged
+    let deleted: Vec<Uuid> = user::Entity::find()
+        .select_only()
+        .column(user::Column::UserId)
+        .filter(user::Column::DeletedAt.lt(cutoff))
+        .lock_exclusive()
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    // users deleted while they were a group's last active owner are kept, so
+    // purging never leaves a group without an owner; restoring them or adding
+    // another owner lets them be purged
+    let others = Alias::new("others");
+    let last_owners: Vec<Uuid> = group_user::Entity::find()
+        .select_only()
+        .column(group_user::Column::UserId)
+        .distinct()
+        .filter(group_user::Column::UserId.is_in(deleted.clone()))
+        .filter(group_user::Column::Role.eq(GROUP_ROLE_OWNER))
+        .filter(
+            Expr::exists(
+                Query::select()
+                    .expr(Expr::value(1))
+                    .from_as(group_user::Entity, others.clone())
+                    .inner_join(
+                        user::Entity,
+                        Expr::col((user::Entity, user::Column::UserId))
+                            .equals((others.clone(), group_user::Column::UserId)),
+                    )
+                    .and_where(
+                        Expr::col((others.clone(), group_user::Column::GroupId))
+                            .equals((group_user::Entity, group_user::Column::GroupId)),
+                    )
+                    .and_where(
+                        Expr::col((others.clone(), group_user::Column::Role)).eq(GROUP_ROLE_OWNER),
+                    )
+                    .and_where(Expr::col((user::Entity, user::Column::DeletedAt)).is_null())
+                    .to_owned(),
+            )
+            .not(),
+        )
+        .into_tuple()
+        .all(&txn)
+        .await?;
+
+    if !last_owners.is_empty() {
+        tracing::warn!(
+            "kept {} deleted users that are the last owner of a group",
+            last_owners.len()
+        );
+    }
+
+    let purged: Vec<Uuid> = deleted
+        .into_iter()
+        .filter(|user_id| !last_owners.contains(user_id))
+        .collect();
+
+    if purged.is_empty() {
+        txn.commit().await?;
+        return Ok(0);
+    }
+
+    group_user::Entity::delete_many()
+        .filter(group_user::Column::UserId.is_in(purged.clone()))
+        .exec(&txn)
+        .await?;
+
+    let res = user::Entity::delete_many()
+        .filter(user::Column::UserId.is_in(purged))
+        .filter(user::Column::DeletedAt.lt(cutoff))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(res.rows_affected)
+}
+
+// purges every `purge_interval` until the server exits; failures are logged
+// and retried on the next tick
+pub fn spawn(conn: Arc<DatabaseConnection>, config: RetentionConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.purge_interval);
+
+        loop {
+            interval.tick().await;
+
+            match purge_deleted_users(&conn, config.deleted_users).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!("purged {} deleted users", purged),
+                Err(err) => tracing::error!("error purging deleted users: {:?}", err),
+            }
+        }
+    });
+}
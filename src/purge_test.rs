@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+    use uuid::Uuid;
+
+    use crate::purge::purge_deleted_users;
+
+    const RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+    fn user_id_row(user_id: Uuid) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([("user_id", user_id.into())])
+    }
+
+    #[tokio::test]
+    async fn test_purge_deleted_users() {
+        let (purged, last_owner) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![user_id_row(purged), user_id_row(last_owner)]])
+            .append_query_results([vec![user_id_row(last_owner)]])
+            .append_exec_results([
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 3,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+            ])
+            .into_connection();
+
+        assert_eq!(purge_deleted_users(&conn, RETENTION).await.unwrap(), 1);
+
+        let log = format!("{:?}", conn.into_transaction_log());
+
+        // the users deleted before the cutoff are locked, then memberships go
+        // first and a group's last owner is kept
+        assert!(log.contains("FOR UPDATE"), "{log}");
+        assert!(
+            log.contains(r#"NOT EXISTS(SELECT $4 FROM \"group_users\" AS \"others\""#),
+            "{log}"
+        );
+        let memberships = log.find(r#"DELETE FROM \"group_users\""#).expect(&log);
+        let users = log.find(r#"DELETE FROM \"users\""#).expect(&log);
+        assert!(memberships < users, "{log}");
+        assert_eq!(log.matches(r#"\"deleted_at\" < "#).count(), 2, "{log}");
+        assert!(log[memberships..].contains(&purged.to_string()), "{log}");
+        assert!(
+            !log[memberships..].contains(&last_owner.to_string()),
+            "{log}"
+        );
+        assert!(log.contains("COMMIT"), "{log}");
+    }
+
+    #[tokio::test]
+    async fn test_purge_keeps_last_owners() {
+        let last_owner = Uuid::new_v4();
+
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![user_id_row(last_owner)]])
+            .append_query_results([vec![user_id_row(last_owner)]])
+            .into_connection();
+
+        assert_eq!(purge_deleted_users(&conn, RETENTION).await.unwrap(), 0);
+
+        let log = format!("{:?}", conn.into_transaction_log());
+        assert!(!log.contains("DELETE"), "{log}");
+    }
+}
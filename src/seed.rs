@@ -0,0 +1,311 @@
+#[path = "seed_test.rs"]
+#[cfg(test)]
+mod seed_test;
+
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveValue::NotSet, DatabaseConnection, DbErr, EntityTrait, Set, TransactionTrait};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::encryption::{self, Encrypted};
+use crate::models::{group, group_user, user};
+
+// rows per insert statement, well below postgres' limit on bind parameters
+const CHUNK_SIZE: usize = 1000;
+
+const USER_ROLES: &[&str] = &["admin", "user"];
+const GROUP_USER_ROLES: &[&str] = &["owner", "admin", "member"];
+
+const FIRST_NAMES: &[&str] = &[
+    "Alex", "Blake", "Casey", "Dana", "Emery", "Finley", "Gray", "Harper", "Indigo", "Jordan",
+    "Kai", "Logan", "Morgan", "Noel", "Oakley", "Parker", "Quinn", "Riley", "Sage", "Taylor",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Adams", "Brooks", "Chen", "Diaz", "Evans", "Fischer", "Garcia", "Hughes", "Ito", "Jensen",
+    "Khan", "Lopez", "Moreau", "Novak", "Okafor", "Patel", "Rossi", "Silva", "Tanaka", "Weber",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Profile {
+    Development,
+    Test,
+    Demo,
+}
+
+impl Profile {
+    // the seed files are embedded so the binary can seed without the repository
+    pub fn source(&self) -> &'static str {
+        match self {
+            Profile::Development => include_str!("../seeders/development.toml"),
+            Profile::Test => include_str!("../seeders/test.toml"),
+            Profile::Demo => include_str!("../seeders/demo.toml"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SeedError {
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    #[error("invalid seed file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("invalid seed data: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedData {
+    #[serde(default)]
+    pub users: Vec<SeedUser>,
+    #[serde(default)]
+    pub groups: Vec<SeedGroup>,
+    #[serde(default)]
+    pub group_users: Vec<SeedGroupUser>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedUser {
+    pub user_id: Uuid,
+    pub role: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub auth0_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedGroup {
+    pub group_id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedGroupUser {
+    pub group_id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+}
+
+impl SeedData {
+    pub fn parse(source: &str) -> Result<SeedData, SeedError> {
+        let data: SeedData = toml::from_str(source)?;
+        data.validate()?;
+        Ok(data)
+    }
+
+    pub fn extend(&mut self, other: SeedData) {
+        self.users.extend(other.users);
+        self.groups.extend(other.groups);
+        self.group_users.extend(other.group_users);
+    }
+
+    // memberships may only refer to users and groups seeded alongside them
+    pub fn validate(&self) -> Result<(), SeedError> {
+        let mut user_ids = HashSet::new();
+        let mut auth0_ids = HashSet::new();
+        for user in &self.users {
+            if !user_ids.insert(user.user_id) {
+                return Err(SeedError::Invalid(format!(
+                    "user {} is seeded twice",
+                    user.user_id
+                )));
+            }
+            if !USER_ROLES.contains(&user.role.as_str()) {
+                return Err(SeedError::Invalid(format!(
+                    "user {} has unknown role {}",
+                    user.user_id, user.role
+                )));
+            }
+            if let Some(auth0_id) = &user.auth0_id {
+                if !auth0_ids.insert(auth0_id) {
+                    return Err(SeedError::Invalid(format!(
+                        "auth0_id {auth0_id} is seeded twice"
+                    )));
+                }
+            }
+        }
+
+        let mut group_ids = HashSet::new();
+        for group in &self.groups {
+            if !group_ids.insert(group.group_id) {
+                return Err(SeedError::Invalid(format!(
+                    "group {} is seeded twice",
+                    group.group_id
+                )));
+            }
+        }
+
+        let mut memberships = HashSet::new();
+        for group_user in &self.group_users {
+            if !group_ids.contains(&group_user.group_id) {
+                return Err(SeedError::Invalid(format!(
+                    "membership refers to unseeded group {}",
+                    group_user.group_id
+                )));
+            }
+            if !user_ids.contains(&group_user.user_id) {
+                return Err(SeedError::Invalid(format!(
+                    "membership refers to unseeded user {}",
+                    group_user.user_id
+                )));
+            }
+            if !GROUP_USER_ROLES.contains(&group_user.role.as_str()) {
+                return Err(SeedError::Invalid(format!(
+                    "membership of user {} in group {} has unknown role {}",
+                    group_user.user_id, group_user.group_id, group_user.role
+                )));
+            }
+            if !memberships.insert((group_user.group_id, group_user.user_id)) {
+                return Err(SeedError::Invalid(format!(
+                    "user {} is seeded into group {} twice",
+                    group_user.user_id, group_user.group_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// generates `users` users and `groups` groups, each group with
+// `members_per_group` of the users and the first of them as its owner; ids
+// are derived from the index so seeding the same numbers again updates
+// rather than duplicates
+pub fn fake(users: usize, groups: usize, members_per_group: usize) -> Result<SeedData, SeedError> {
+    if groups > 0 && members_per_group > users {
+        return Err(SeedError::Invalid(format!(
+            "can't add {members_per_group} members to each group from {users} users"
+        )));
+    }
+
+    let users = (0..users)
+        .map(|index| SeedUser {
+            user_id: fake_id("user", index),
+            role: "user".to_owned(),
+            first_name: Some(FIRST_NAMES[index % FIRST_NAMES.len()].to_owned()),
+            last_name: Some(LAST_NAMES[(index / FIRST_NAMES.len()) % LAST_NAMES.len()].to_owned()),
+            auth0_id: Some(format!("fake|user-{index}")),
+        })
+        .collect::<Vec<SeedUser>>();
+
+    let groups = (0..groups)
+        .map(|index| SeedGroup {
+            group_id: fake_id("group", index),
+            name: format!("Fake Group {index}"),
+        })
+        .collect::<Vec<SeedGroup>>();
+
+    // consecutive users, wrapping around, so memberships spread evenly
+    let group_users = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(index, group)| {
+            let users = &users;
+            (0..members_per_group).map(move |member| SeedGroupUser {
+                group_id: group.group_id,
+                user_id: users[(index * members_per_group + member) % users.len()].user_id,
+                role: match member {
+                    0 => "owner".to_owned(),
+                    _ => "member".to_owned(),
+                },
+            })
+        })
+        .collect::<Vec<SeedGroupUser>>();
+
+    Ok(SeedData {
+        users,
+        groups,
+        group_users,
+    })
+}
+
+fn fake_id(kind: &str, index: usize) -> Uuid {
+    let digest = Sha256::digest(format!("fake-{kind}-{index}").as_bytes());
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_random_bytes(bytes).into_uuid()
+}
+
+// upserts everything in one transaction, so seeding either fully applies or
+// leaves the database untouched and can be rerun at any time
+pub async fn seed(conn: &DatabaseConnection, data: &SeedData) -> Result<(), SeedError> {
+    data.validate()?;
+
+    let txn = conn.begin().await?;
+
+    for users in data.users.chunks(CHUNK_SIZE) {
+        user::Entity::insert_many(users.iter().map(|seed| user::ActiveModel {
+            user_id: Set(seed.user_id),
+            role: Set(seed.role.to_owned()),
+            first_name: Set(seed.first_name.to_owned().map(Encrypted::from)),
+            last_name: Set(seed.last_name.to_owned().map(Encrypted::from)),
+            auth0_id: Set(seed.auth0_id.to_owned().map(Encrypted::from)),
+            // insert_many skips before_save, which maintains the index
+            auth0_id_index: Set(seed.auth0_id.as_deref().map(encryption::blind_index)),
+            ..Default::default()
+        }))
+        .on_conflict(
+            OnConflict::column(user::Column::UserId)
+                // only the sign-in identity is refreshed, so reseeding never
+                // restores a deleted user or reverts a role or name change
+                .update_columns([user::Column::Auth0Id, user::Column::Auth0IdIndex])
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+    }
+
+    for groups in data.groups.chunks(CHUNK_SIZE) {
+        group::Entity::insert_many(groups.iter().map(|seed| group::ActiveModel {
+            group_id: Set(seed.group_id),
+            name: Set(seed.name.to_owned()),
+            ..Default::default()
+        }))
+        .on_conflict(
+            OnConflict::column(group::Column::GroupId)
+                .update_column(group::Column::Name)
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+    }
+
+    // memberships are keyed by the unique (group_id, user_id) index, the
+    // group_user_id is left to the database
+    for group_users in data.group_users.chunks(CHUNK_SIZE) {
+        group_user::Entity::insert_many(group_users.iter().map(|seed| group_user::ActiveModel {
+            group_user_id: NotSet,
+            group_id: Set(seed.group_id),
+            user_id: Set(seed.user_id),
+            role: Set(seed.role.to_owned()),
+            ..Default::default()
+        }))
+        .on_conflict(
+            OnConflict::columns([group_user::Column::GroupId, group_user::Column::UserId])
+                .update_column(group_user::Column::Role)
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    tracing::info!(
+        "seeded {} users, {} groups and {} memberships",
+        data.users.len(),
+        data.groups.len(),
+        data.group_users.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use clap::ValueEnum;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    use crate::seed::{fake, seed, Profile, SeedData, SeedError};
+
+    #[test]
+    fn test_profiles_are_valid() {
+        for profile in Profile::value_variants() {
+            let data = SeedData::parse(profile.source())
+                .unwrap_or_else(|err| panic!("{profile:?}: {err}"));
+            assert!(!data.users.is_empty(), "{profile:?}");
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_data() {
+        let unknown_role = r#"
+            [[users]]
+            user_id = "00000000-0000-4000-8000-000000000001"
+            role = "superuser"
+        "#;
+        assert!(matches!(
+            SeedData::parse(unknown_role),
+            Err(SeedError::Invalid(_))
+        ));
+
+        let unseeded_group = r#"
+            [[users]]
+            user_id = "00000000-0000-4000-8000-000000000001"
+            role = "user"
+
+            [[group_users]]
+            group_id = "00000000-0000-4000-8000-000000000101"
+            user_id = "00000000-0000-4000-8000-000000000001"
+            role = "member"
+        "#;
+        assert!(matches!(
+            SeedData::parse(unseeded_group),
+            Err(SeedError::Invalid(_))
+        ));
+
+        let unknown_field = r#"
+            [[groups]]
+            group_id = "00000000-0000-4000-8000-000000000101"
+            name = "Group"
+            owner = "someone"
+        "#;
+        assert!(matches!(
+            SeedData::parse(unknown_field),
+            Err(SeedError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_fake() {
+        let data = fake(10, 4, 3).unwrap();
+
+        assert_eq!(data.users.len(), 10);
+        assert_eq!(data.groups.len(), 4);
+        assert_eq!(data.group_users.len(), 12);
+        assert!(data.validate().is_ok());
+
+        // every group has exactly one owner
+        let owners = data
+            .group_users
+            .iter()
+            .filter(|group_user| group_user.role == "owner")
+            .map(|group_user| group_user.group_id)
+            .collect::<HashSet<_>>();
+        assert_eq!(owners.len(), 4);
+
+        // the same numbers generate the same ids, so reseeding upserts
+        let again = fake(10, 4, 3).unwrap();
+        assert_eq!(data.users[7].user_id, again.users[7].user_id);
+        assert_eq!(data.groups[3].group_id, again.groups[3].group_id);
+
+        assert!(matches!(fake(2, 1, 3), Err(SeedError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn test_seed_upserts_in_a_transaction() {
+        let conn = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results((0..3).map(|_| MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }))
+            .into_connection();
+
+        seed(&conn, &SeedData::parse(Profile::Test.source()).unwrap())
+            .await
+            .unwrap();
+
+        let log = format!("{:?}", conn.into_transaction_log());
+
+        assert!(log.contains(r#"INSERT INTO \"users\""#), "{log}");
+        assert!(
+            log.contains(r#"ON CONFLICT (\"user_id\") DO UPDATE"#),
+            "{log}"
+        );
+
+        let users = log.split(r#"INSERT INTO \"groups\""#).next().unwrap();
+        assert!(!users.contains(r#"\"deleted_at\" = "#), "{users}");
+        assert!(!users.contains(r#"\"role\" = "#), "{users}");
+        assert!(
+            log.contains(r#"ON CONFLICT (\"group_id\") DO UPDATE"#),
+            "{log}"
+        );
+        assert!(
+            log.contains(r#"ON CONFLICT (\"group_id\", \"user_id\") DO UPDATE"#),
+            "{log}"
+        );
+        assert!(log.contains("COMMIT"), "{log}");
+    }
+}
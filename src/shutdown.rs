@@ -0,0 +1,101 @@
+#[path = "shutdown_test.rs"]
+#[cfg(test)]
+mod shutdown_test;
+
+use std::future::{Future, IntoFuture};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use crate::config::ShutdownConfig;
+
+// shared with the readiness check, which fails once draining starts so load
+// balancers stop routing new requests before the listener closes
+#[derive(Clone, Default)]
+pub struct Readiness {
+    draining: Arc<AtomicBool>,
+}
+
+impl Readiness {
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+}
+
+// resolves on the first SIGTERM or SIGINT, with the name of the signal
+pub async fn signal() -> &'static str {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install the SIGINT handler");
+        "SIGINT"
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install the SIGTERM handler")
+            .recv()
+            .await;
+        "SIGTERM"
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<&'static str>();
+
+    tokio::select! {
+        signal = interrupt => signal,
+        signal = terminate => signal,
+    }
+}
+
+// serves until `signal` resolves, then fails readiness, keeps accepting for
+// the readiness delay, stops accepting and waits up to the drain timeout for
+// in-flight requests before giving up on them
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    readiness: Readiness,
+    config: &ShutdownConfig,
+    signal: impl Future<Output = &'static str> + Send + 'static,
+) -> std::io::Result<()> {
+    let readiness_delay = config.readiness_delay;
+    let (closed_tx, closed_rx) = oneshot::channel();
+
+    let server =
+        axum::serve(listener, router.into_make_service()).with_graceful_shutdown(async move {
+            let signal = signal.await;
+            tracing::info!("received {}, failing readiness", signal);
+            readiness.start_draining();
+
+            tokio::time::sleep(readiness_delay).await;
+
+            tracing::info!("closing the listener and draining in-flight requests");
+            let _ = closed_tx.send(());
+        });
+
+    let deadline = async {
+        match closed_rx.await {
+            Ok(()) => tokio::time::sleep(config.drain_timeout).await,
+            Err(_) => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        res = server.into_future() => res,
+        _ = deadline => {
+            tracing::warn!(
+                "in-flight requests did not finish within {:?}, shutting down",
+                config.drain_timeout
+            );
+            Ok(())
+        }
+    }
+}
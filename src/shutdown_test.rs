@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use axum::{routing::get, Router};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    use crate::config::ShutdownConfig;
+    use crate::shutdown::{serve, Readiness};
+
+    // serves a route that takes `handler_delay`, returning the address, the
+    // readiness handle, a sender that stands in for the signal and the
+    // server task
+    async fn start(
+        handler_delay: Duration,
+        config: ShutdownConfig,
+    ) -> (
+        String,
+        Readiness,
+        oneshot::Sender<()>,
+        tokio::task::JoinHandle<std::io::Result<()>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        let router = Router::new().route(
+            "/slow",
+            get(move || async move {
+                tokio::time::sleep(handler_delay).await;
+                "done"
+            }),
+        );
+
+        let readiness = Readiness::default();
+        let (signal_tx, signal_rx) = oneshot::channel::<()>();
+
+        let server = tokio::spawn({
+            let readiness = readiness.clone();
+            async move {
+                let signal = async move {
+                    let _ = signal_rx.await;
+                    "SIGTERM"
+                };
+                serve(listener, router, readiness, &config, signal).await
+            }
+        });
+
+        (address, readiness, signal_tx, server)
+    }
+
+    #[tokio::test]
+    async fn test_drains_in_flight_requests() {
+        let (address, readiness, signal_tx, server) = start(
+            Duration::from_millis(300),
+            ShutdownConfig {
+                readiness_delay: Duration::from_millis(50),
+                drain_timeout: Duration::from_secs(5),
+            },
+        )
+        .await;
+
+        let request = tokio::spawn(reqwest::get(format!("{address}/slow")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(!readiness.is_draining());
+        signal_tx.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(readiness.is_draining());
+
+        let response = request.await.unwrap().unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "done");
+
+        server.await.unwrap().unwrap();
+
+        // the listener is closed once draining finishes
+        assert!(reqwest::get(format!("{address}/slow")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_drain_timeout() {
+        let (address, _, signal_tx, server) = start(
+            Duration::from_secs(60),
+            ShutdownConfig {
+                readiness_delay: Duration::ZERO,
+                drain_timeout: Duration::from_millis(100),
+            },
+        )
+        .await;
+
+        let request = tokio::spawn(reqwest::get(format!("{address}/slow")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let started = Instant::now();
+        signal_tx.send(()).unwrap();
+        server.await.unwrap().unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        request.abort();
+    }
+}
@@ -0,0 +1,176 @@
+#[path = "telemetry_test.rs"]
+#[cfg(test)]
+mod telemetry_test;
+
+use std::time::SystemTime;
+
+use anyhow::anyhow;
+use axum::{body::Body, extract::MatchedPath, http::Request};
+use opentelemetry::propagation::Injector;
+use opentelemetry::trace::{Span, SpanKind, Tracer, TracerProvider as _};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::logging::{self, LogFormat};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const DEFAULT_SERVICE_NAME: &str = "rust_server";
+const TRACER_NAME: &str = "rust_server";
+
+// owns the exporter pipeline so buffered spans can be flushed on shutdown
+pub struct Telemetry {
+    provider: Option<TracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(&self) {
+        if let Some(provider) = &self.provider {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("error shutting down the tracer provider: {:?}", err);
+            }
+        }
+    }
+}
+
+// builds a provider exporting over OTLP/HTTP to `endpoint`, the collector
+// base url, e.g. "http://localhost:4318"
+pub fn tracer_provider(endpoint: &str, service_name: &str) -> anyhow::Result<TracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+        .map_err(|err| anyhow!(err))?;
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            service_name.to_owned(),
+        )]))
+        .build())
+}
+
+// installs the global subscriber, filtered by RUST_LOG; spans are only
+// exported when an OTLP endpoint is configured, trace context is propagated
+// either way
+pub fn init(
+    otlp_endpoint: Option<&str>,
+    service_name: &str,
+    log_format: LogFormat,
+) -> anyhow::Result<Telemetry> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let provider = otlp_endpoint
+        .map(|endpoint| tracer_provider(endpoint, service_name))
+        .transpose()?;
+
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(TRACER_NAME)));
+
+    if let Some(provider) = &provider {
+        global::set_tracer_provider(provider.clone());
+    }
+
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(logging::DEFAULT_FILTER));
+
+    tracing_subscriber::registry()
+        .with(logging::fmt_layer(log_format, std::io::stdout))
+        .with(filter)
+        .with(otel_layer)
+        .try_init()
+        .map_err(|err| anyhow!(err))?;
+
+    Ok(Telemetry { provider })
+}
+
+// the span for an incoming request, continuing the caller's trace when the
+// request carries a traceparent header
+pub fn make_request_span(req: &Request<Body>) -> tracing::Span {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| req.uri().path().to_owned());
+
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        otel.kind = "server",
+        http.request.method = %req.method(),
+        http.route = %route,
+        url.path = %req.uri().path(),
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(req.headers()))
+    });
+    span.set_parent(parent);
+
+    span
+}
+
+struct ReqwestHeaderInjector<'a>(&'a mut reqwest::header::HeaderMap);
+
+impl Injector for ReqwestHeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+            reqwest::header::HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+// the traceparent headers for an outgoing request made within the current span
+pub fn trace_headers() -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut ReqwestHeaderInjector(&mut headers))
+    });
+    headers
+}
+
+// sea-orm reports queries after they complete, so the span is recorded
+// retroactively from the elapsed time, as a child of the current span
+pub fn record_query(info: &sea_orm::metric::Info<'_>) {
+    let end = SystemTime::now();
+    let start = end.checked_sub(info.elapsed).unwrap_or(end);
+    let sql = info.statement.sql.as_str();
+    let operation = sql
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+
+    let tracer = global::tracer(TRACER_NAME);
+    let mut span = tracer
+        .span_builder(format!("db {operation}"))
+        .with_kind(SpanKind::Client)
+        .with_start_time(start)
+        .with_attributes(vec![
+            KeyValue::new("db.system", "postgresql"),
+            KeyValue::new("db.operation.name", operation),
+            KeyValue::new("db.query.text", sql.to_owned()),
+            KeyValue::new("error", info.failed),
+        ])
+        .start_with_context(&tracer, &tracing::Span::current().context());
+    span.end_with_timestamp(end);
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        body::{Body, Bytes},
+        extract::State,
+        http::{HeaderMap, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use opentelemetry::global;
+    use opentelemetry::trace::{Span, Tracer, TracerProvider as _};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::telemetry::{make_request_span, trace_headers, tracer_provider};
+
+    type Received = Arc<Mutex<Vec<(String, Bytes)>>>;
+
+    // stands in for an OTLP/HTTP collector, recording every export it receives
+    async fn start_collector() -> (String, Received) {
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+
+        let app = Router::new()
+            .route(
+                "/v1/traces",
+                post(
+                    |State(received): State<Received>, headers: HeaderMap, body: Bytes| async move {
+                        let content_type = headers
+                            .get("content-type")
+                            .and_then(|value| value.to_str().ok())
+                            .unwrap_or_default()
+                            .to_owned();
+                        received.lock().unwrap().push((content_type, body));
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .with_state(received.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{addr}"), received)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_export_to_collector() {
+        let (endpoint, received) = start_collector().await;
+
+        let provider = tracer_provider(&endpoint, "rust_server_test").unwrap();
+        let mut span = provider.tracer("test").start("test_span");
+        span.end();
+
+        for res in provider.force_flush() {
+            res.unwrap();
+        }
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0, "application/x-protobuf");
+        assert!(!received[0].1.is_empty());
+
+        provider.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_trace_context_propagation() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let provider = TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let headers = tracing::subscriber::with_default(subscriber, || {
+            let req = Request::builder()
+                .uri("/users/me")
+                .header(
+                    "traceparent",
+                    "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+                )
+                .body(Body::empty())
+                .unwrap();
+
+            make_request_span(&req).in_scope(trace_headers)
+        });
+
+        let traceparent = headers.get("traceparent").unwrap().to_str().unwrap();
+        assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(!traceparent.contains("00f067aa0ba902b7"));
+    }
+}
@@ -1,8 +1,10 @@
 use std::future;
 
 use mockall::predicate::*;
+use uuid::Uuid;
 
 use crate::authentication::{self, IAuthentication};
+use crate::models;
 
 const DEFAULT_AUTH0_ID: &str = "default_auth0_id";
 const DEFAULT_AUTH0_TOKEN: &str = "default_auth0_token";
@@ -28,3 +30,40 @@ pub fn get_default_auth() -> Box<dyn IAuthentication> {
 
     Box::new(auth)
 }
+
+// a user signed in with the default auth header
+pub fn get_user_db(role: &str) -> models::user::Model {
+    models::user::Model {
+        user_id: Uuid::new_v4(),
+        first_name: Some("first_name".into()),
+        last_name: Some("last_name".into()),
+        auth0_id: Some(DEFAULT_AUTH0_ID.into()),
+        auth0_id_index: None,
+        role: role.to_owned(),
+        created_at: chrono::Utc::now().into(),
+        updated_at: chrono::Utc::now().into(),
+        deleted_at: None,
+        version: 1,
+    }
+}
+
+pub fn get_group_db(name: &str) -> models::group::Model {
+    models::group::Model {
+        group_id: Uuid::new_v4(),
+        name: name.to_owned(),
+        created_at: chrono::Utc::now().into(),
+        updated_at: chrono::Utc::now().into(),
+        version: 1,
+    }
+}
+
+pub fn get_group_user_db(group_id: Uuid, user_id: Uuid, role: &str) -> models::group_user::Model {
+    models::group_user::Model {
+        group_user_id: Uuid::new_v4(),
+        group_id,
+        user_id,
+        role: role.to_owned(),
+        created_at: chrono::Utc::now().into(),
+        updated_at: chrono::Utc::now().into(),
+    }
+}
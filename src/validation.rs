@@ -0,0 +1,132 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::errors::ServerError;
+
+pub const MAX_NAME_LENGTH: usize = 100;
+pub const MAX_AUTH0_ID_LENGTH: usize = 255;
+
+// auth0 user ids are "<connection>|<id>", e.g. "auth0|5f7c8ec7c33c6c004bbafe82"
+static AUTH0_ID_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_-]+\|[A-Za-z0-9._:@-]+$").unwrap());
+
+// a single failing field, `code` is stable and meant for clients to match on
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.to_owned(),
+            code: code.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+pub trait Validate {
+    fn validate(&self) -> Result<(), ServerError>;
+}
+
+// collects every failing field instead of stopping at the first one
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, error: FieldError) -> &mut Self {
+        self.errors.push(error);
+        self
+    }
+
+    pub fn text(&mut self, field: &str, value: &str, max_length: usize) -> &mut Self {
+        if value.trim().is_empty() {
+            return self.add(FieldError::new(field, "blank", "must not be blank"));
+        }
+        if value.chars().count() > max_length {
+            return self.add(FieldError::new(
+                field,
+                "too_long",
+                format!("must be at most {max_length} characters"),
+            ));
+        }
+        self
+    }
+
+    pub fn optional_text(
+        &mut self,
+        field: &str,
+        value: Option<&str>,
+        max_length: usize,
+    ) -> &mut Self {
+        match value {
+            Some(value) => self.text(field, value, max_length),
+            None => self,
+        }
+    }
+
+    pub fn auth0_id(&mut self, field: &str, value: &str) -> &mut Self {
+        let errors = self.errors.len();
+        self.text(field, value, MAX_AUTH0_ID_LENGTH);
+        if self.errors.len() == errors && !AUTH0_ID_PATTERN.is_match(value) {
+            self.add(FieldError::new(
+                field,
+                "invalid_format",
+                "must look like \"<connection>|<id>\"",
+            ));
+        }
+        self
+    }
+
+    pub fn one_of(&mut self, field: &str, value: &str, allowed: &[&str]) -> &mut Self {
+        if !allowed.contains(&value) {
+            self.add(FieldError::new(
+                field,
+                "invalid_choice",
+                format!("must be one of: {}", allowed.join(", ")),
+            ));
+        }
+        self
+    }
+
+    pub fn range(&mut self, field: &str, value: u64, min: u64, max: u64) -> &mut Self {
+        if !(min..=max).contains(&value) {
+            self.add(FieldError::new(
+                field,
+                "out_of_range",
+                format!("must be between {min} and {max}"),
+            ));
+        }
+        self
+    }
+
+    pub fn finish(&mut self) -> Result<(), ServerError> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        Err(ServerError::Validation(std::mem::take(&mut self.errors)))
+    }
+}
+
+pub fn parse_uuid(field: &str, value: &str) -> Result<Uuid, ServerError> {
+    Uuid::parse_str(value).map_err(|err| {
+        ServerError::Validation(vec![FieldError::new(
+            field,
+            "invalid_uuid",
+            err.to_string(),
+        )])
+    })
+}